@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use ratatui::backend::TestBackend;
+use ratatui::layout::Rect;
+use ratatui::Terminal;
+use rustbonsai::app::{App, CanvasMarker, Settings, TICK_RATE};
+use rustbonsai::bonsai::BonsaiTree;
+use rustbonsai::bonsai::growth::GrowthStyle;
+use rustbonsai::palette::ColorMode;
+
+const SEED: u64 = 42;
+
+// a small laptop split, a regular terminal and a large one
+const SIZES: [(u16, u16); 3] = [(40, 16), (120, 40), (240, 70)];
+const TIERS: [u32; 3] = [1, 2, 3];
+
+fn tree(size: (u16, u16), live: bool) -> BonsaiTree {
+    BonsaiTree::new(Rect::new(0, 0, size.0, size.1), Some(SEED), live, None, 0.0, GrowthStyle::default())
+}
+
+fn generated(size: (u16, u16), tiers: u32, live: bool) -> BonsaiTree {
+    let mut tree = tree(size, live);
+    tree.generate_tiers(tiers);
+
+    tree
+}
+
+fn grown(size: (u16, u16), tiers: u32, live: bool) -> BonsaiTree {
+    let mut tree = generated(size, tiers, live);
+    tree.normalize();
+
+    tree
+}
+
+fn settings(size: (u16, u16), live: bool) -> (Settings, Rect) {
+    let settings = Settings { seed: Some(SEED), live, color: ColorMode::Truecolor, marker: CanvasMarker::Braille, ..Settings::default() };
+
+    (settings, Rect::new(0, 0, size.0, size.1))
+}
+
+// every case is named by its size and tier count, e.g. "generate/120x40/2"
+fn cases() -> impl Iterator<Item = ((u16, u16), u32, String)> {
+    SIZES.into_iter().flat_map(|size| TIERS.into_iter().map(move |tiers| (size, tiers, format!("{}x{}/{}", size.0, size.1, tiers))))
+}
+
+fn generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate");
+
+    for (size, tiers, name) in cases() {
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter_batched(|| tree(size, false), |mut tree| { tree.generate_tiers(tiers); tree }, BatchSize::SmallInput)
+        });
+    }
+}
+
+fn normalize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("normalize");
+
+    for (size, tiers, name) in cases() {
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter_batched(|| generated(size, tiers, false), |mut tree| { tree.normalize(); tree }, BatchSize::SmallInput)
+        });
+    }
+}
+
+fn points(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_tree");
+
+    for (size, tiers, name) in cases() {
+        let tree = grown(size, tiers, false);
+        group.bench_function(BenchmarkId::from_parameter(name), |b| b.iter(|| tree.get_tree()));
+    }
+
+    group.finish();
+
+    let mut group = c.benchmark_group("get_leaves");
+
+    for (size, tiers, name) in cases() {
+        let tree = grown(size, tiers, false);
+        group.bench_function(BenchmarkId::from_parameter(name), |b| b.iter(|| tree.get_leaves()));
+    }
+}
+
+fn animation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("animation");
+
+    for (size, tiers, name) in cases() {
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter_batched(
+                || grown(size, tiers, true),
+                |mut tree| {
+                    while !tree.is_grown() {
+                        tree.animation_step();
+                    }
+
+                    tree
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    group.sample_size(20);
+
+    for size in SIZES {
+        let name = format!("{}x{}", size.0, size.1);
+        let mut terminal = Terminal::new(TestBackend::new(size.0, size.1)).unwrap();
+
+        let (still, area) = settings(size, false);
+        let app = App::new(&still, area).unwrap();
+
+        group.bench_function(BenchmarkId::new("static", &name), |b| {
+            b.iter(|| terminal.draw(|frame| app.ui(frame)).map(|_| ()).unwrap())
+        });
+
+        // the whole growth, a tick and the frame drawn after it as the event loop does
+        let (live, area) = settings(size, true);
+        let steps = grown(size, 2, true).growth_steps();
+
+        group.bench_function(BenchmarkId::new("live", &name), |b| {
+            b.iter_batched(
+                || App::new(&live, area).unwrap(),
+                |mut app| {
+                    for _ in 0..steps {
+                        app.tick(Duration::from_millis(TICK_RATE), None);
+                        terminal.draw(|frame| app.ui(frame)).unwrap();
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, generate, normalize, points, animation, render);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+use ratatui::layout::Rect;
+use rustbonsai::animator::Animator;
+use rustbonsai::bonsai::BonsaiTree;
+use rustbonsai::bonsai::growth::GrowthStyle;
+
+// grows a tree without a terminal and prints what every frame of the animation adds
+fn main() {
+    let mut tree = BonsaiTree::new(Rect::new(0, 0, 120, 40), Some(42), true, None, 0.0, GrowthStyle::default());
+    tree.generate();
+    tree.normalize();
+
+    let mut animator = Animator::new(tree);
+    println!("total steps: {}", animator.total_steps());
+
+    while !animator.is_finished() {
+        let frame = animator.next_frame();
+
+        println!(
+            "step {:>3}: {:>2} segments, {:>3} leaves, {:>3} particles, {:>3} removed",
+            frame.step,
+            frame.segments.len(),
+            frame.leaves.len(),
+            frame.particles.len(),
+            frame.removed_particles.len(),
+        );
+    }
+
+    animator.seek(animator.total_steps() / 2);
+    let half = animator.snapshot();
+    println!("halfway: {} segments, {} leaves", half.segments.len(), half.leaves.len());
+}
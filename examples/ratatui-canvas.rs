@@ -96,7 +96,7 @@ impl App {
     fn on_tick(&mut self) {
         self.tick_count += 1;
         // only change marker every 180 ticks (3s) to avoid stroboscopic effect
-        if (self.tick_count % 180) == 0 {
+        if self.tick_count.is_multiple_of(180) {
             self.marker = match self.marker {
                 Marker::Dot => Marker::Braille,
                 Marker::Braille => Marker::Block,
@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+
+use crate::bonsai::{BonsaiTree, PointType};
+use crate::bonsai::utils::Point;
+
+/// Everything one step of the animation changed, in canvas coordinates.
+#[derive(Debug, Clone, Default)]
+pub struct FrameDelta {
+    pub step: usize,
+    /// Newly grown pieces of branches, from start to end point.
+    pub segments: Vec <(Point, Point)>,
+    pub leaves: Vec <Point>,
+    pub blooms: Vec <Point>,
+    /// Current position of every live particle, as particle, fading particle or debris.
+    pub particles: Vec <(u64, PointType)>,
+    /// Particles that left the canvas or faded out since the previous frame.
+    pub removed_particles: Vec <u64>,
+}
+
+/// Drives the growth of a [`BonsaiTree`] without drawing anything, so that any
+/// front end can render the frames its own way.
+pub struct Animator {
+    tree: BonsaiTree,
+
+    step: usize,
+    total_steps: usize,
+
+    particle_ids: HashSet <u64>,
+}
+
+impl Animator {
+    /// Takes a generated and normalized tree, the animation starts from its current state.
+    pub fn new(tree: BonsaiTree) -> Self {
+        Animator {
+            total_steps: tree.growth_steps(),
+            step: tree.current_step(),
+            tree,
+
+            particle_ids: HashSet::new(),
+        }
+    }
+
+    pub fn tree(&self) -> &BonsaiTree {
+        &self.tree
+    }
+
+    pub fn current_step(&self) -> usize {
+        self.step
+    }
+
+    /// Steps it takes for the tree to fully grow, particles keep falling afterwards.
+    pub fn total_steps(&self) -> usize {
+        self.total_steps
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step >= self.total_steps
+    }
+
+    pub fn next_frame(&mut self) -> FrameDelta {
+        let before = self.tree.growth();
+
+        self.tree.animation_step();
+        self.step += 1;
+
+        let (segments, leaves, blooms) = self.tree.grown_since(Some(&before));
+
+        FrameDelta {
+            step: self.step,
+            segments,
+            leaves,
+            blooms,
+            ..self.particles_frame()
+        }
+    }
+
+    /// Moves the animation to `step`, jumping straight to it along the growth and
+    /// stepping the particles forward past it. Use [`Animator::snapshot`] afterwards
+    /// to draw the whole tree at that step.
+    pub fn seek(&mut self, step: usize) {
+        if step < self.step || step <= self.total_steps {
+            self.tree.seek(step);
+            self.step = usize::min(step, self.total_steps);
+        }
+
+        while self.step < step {
+            self.tree.animation_step();
+            self.step += 1;
+        }
+
+        self.particle_ids.clear();
+    }
+
+    /// Everything visible at the current step, as if it all appeared in one frame.
+    pub fn snapshot(&mut self) -> FrameDelta {
+        let (segments, leaves, blooms) = self.tree.grown_since(None);
+
+        FrameDelta {
+            step: self.step,
+            segments,
+            leaves,
+            blooms,
+            ..self.particles_frame()
+        }
+    }
+
+    fn particles_frame(&mut self) -> FrameDelta {
+        let particles = self.tree.particles();
+        let ids: HashSet <u64> = particles.iter().map(|&(id, _)| id).collect();
+
+        let mut removed_particles: Vec <u64> = self.particle_ids.difference(&ids).copied().collect();
+        removed_particles.sort_unstable();
+
+        self.particle_ids = ids;
+
+        FrameDelta {
+            step: self.step,
+            particles,
+            removed_particles,
+            ..FrameDelta::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+
+    use super::*;
+    use crate::bonsai::growth::GrowthStyle;
+
+    fn animator(seed: u64) -> Animator {
+        let mut tree = BonsaiTree::new(Rect::new(0, 0, 80, 30), Some(seed), true, None, 0.3, GrowthStyle::default());
+        tree.set_roots(true);
+        tree.generate();
+        tree.normalize();
+
+        Animator::new(tree)
+    }
+
+    // points rounded so sums of floats compare equal
+    fn sorted(points: &[Point]) -> Vec <(i64, i64)> {
+        let mut points: Vec <_> = points.iter().map(|p| ((p.x * 1e6).round() as i64, (p.y * 1e6).round() as i64)).collect();
+        points.sort_unstable();
+
+        points
+    }
+
+    fn length(segments: &[(Point, Point)]) -> f64 {
+        segments.iter().map(|(a, b)| f64::hypot(b.x - a.x, b.y - a.y)).sum()
+    }
+
+    #[test]
+    fn deltas_add_up_to_the_grown_tree() {
+        let mut animator = animator(42);
+        let mut grown = FrameDelta::default();
+
+        while !animator.is_finished() {
+            let frame = animator.next_frame();
+
+            grown.segments.extend(frame.segments);
+            grown.leaves.extend(frame.leaves);
+            grown.blooms.extend(frame.blooms);
+        }
+
+        let tree = animator.snapshot();
+
+        assert!(!tree.leaves.is_empty() && !tree.blooms.is_empty());
+        assert_eq!(sorted(&grown.leaves), sorted(&tree.leaves));
+        assert_eq!(sorted(&grown.blooms), sorted(&tree.blooms));
+        assert!(f64::abs(length(&grown.segments) - length(&tree.segments)) < 1e-6);
+    }
+
+    #[test]
+    fn seek_reproduces_the_frame() {
+        let mut stepped = animator(7);
+        let mut seeked = animator(7);
+
+        let total = stepped.total_steps();
+        for step in [total / 3, total / 2, total] {
+            while stepped.current_step() < step {
+                stepped.next_frame();
+            }
+
+            // forward from where it is, and back from the end
+            for from in [0, total] {
+                seeked.seek(from);
+                seeked.seek(step);
+
+                let (expected, found) = (stepped.snapshot(), seeked.snapshot());
+
+                assert_eq!(found.step, step);
+                assert_eq!(sorted(&found.leaves), sorted(&expected.leaves));
+                assert_eq!(sorted(&found.blooms), sorted(&expected.blooms));
+                assert!(f64::abs(length(&found.segments) - length(&expected.segments)) < 1e-6);
+            }
+        }
+    }
+}
@@ -1,185 +1,416 @@
 use std::io;
 use std::io::{Stdout, stdout};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 use crossterm::{event, ExecutableCommand};
-use crossterm::event::{Event, KeyCode};
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, MouseButton, MouseEventKind};
+use crossterm::terminal::{self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::CrosstermBackend;
 use ratatui::{Frame, Terminal};
-use ratatui::layout::Rect;
-use ratatui::prelude::{Color, Marker, Widget};
-use ratatui::widgets::{Block, Borders};
-use ratatui::widgets::canvas::{Canvas, Points};
-use crate::bonsai::{BonsaiTree, PointType};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::symbols::Marker;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use crate::bonsai::growth::{Easing, GrowthOrder, LeafReveal};
+use crate::error::BonsaiError;
+use crate::palette::ColorMode;
+use crate::plot::{self, Plot};
+use crate::saved::{self, Favorites, SavedTree};
+use crate::weather::WeatherKind;
+
+pub const TICK_RATE: u64 = 50;
+
+const THEMES: u16 = 4;
+
+// smallest plot, border included, that still fits a recognizable tree and its pot
+const MIN_PLOT_SIZE: (u16, u16) = (16, 8);
+
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bloom {
+    None,
+    Flowers,
+    Fruit,
+}
 
-const TICK_RATE: u64 = 50;
+impl Bloom {
+    /// Symbol printed for every bloom, none when the tree doesn't bloom.
+    pub fn glyph(self) -> Option<&'static str> {
+        match self {
+            Bloom::None => None,
+            Bloom::Flowers => Some("✿"),
+            Bloom::Fruit => Some("●"),
+        }
+    }
+}
 
-pub struct App<'a> {
-    tree_points: Points<'a>,
+/// Symbol the canvas draws points with, some fit several points into one cell.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanvasMarker {
+    Dot,
+    Braille,
+    Block,
+    HalfBlock,
+    Bar,
+}
 
-    leaf_points: Points<'a>,
+impl CanvasMarker {
+    pub fn marker(self) -> Marker {
+        match self {
+            CanvasMarker::Dot => Marker::Dot,
+            CanvasMarker::Braille => Marker::Braille,
+            CanvasMarker::Block => Marker::Block,
+            CanvasMarker::HalfBlock => Marker::HalfBlock,
+            CanvasMarker::Bar => Marker::Bar,
+        }
+    }
 
-    pot_points: Points<'a>,
+    /// Dots one terminal cell can show separately, horizontally and vertically.
+    pub fn resolution(self) -> (u16, u16) {
+        match self {
+            CanvasMarker::Braille => (2, 4),
+            CanvasMarker::HalfBlock => (1, 2),
+            _ => (1, 1),
+        }
+    }
 
-    particles: Points <'a>,
+    pub fn next(self) -> Self {
+        match self {
+            CanvasMarker::Dot => CanvasMarker::Braille,
+            CanvasMarker::Braille => CanvasMarker::Block,
+            CanvasMarker::Block => CanvasMarker::HalfBlock,
+            CanvasMarker::HalfBlock => CanvasMarker::Bar,
+            CanvasMarker::Bar => CanvasMarker::Dot,
+        }
+    }
+}
 
-    marker: Marker,
+#[derive(Clone)]
+pub struct Settings {
+    pub seed: Option<u64>,
+    pub live: bool,
+    pub theme: u16,
+    pub particles: bool,
+    pub weather: WeatherKind,
+    pub seasons: Option<u32>,
+    pub bloom: Bloom,
+    pub bloom_density: f64,
+    pub grow_realtime: bool,
+    pub save: Option<PathBuf>,
+    pub count: u16,
+    pub duration: Option<f64>,
+    pub easing: Easing,
+    pub growth_order: GrowthOrder,
+    pub leaf_reveal: LeafReveal,
+    pub gradient: bool,
+    pub color: ColorMode,
+    pub marker: CanvasMarker,
+    pub roots: bool,
+    pub browse: bool,
+    pub favorites: Option<PathBuf>,
+}
 
-    bounds: (f64, f64),
+/// The defaults of the command line options: a static tree from a random seed.
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            seed: None,
+            live: false,
+            theme: 1,
+            particles: false,
+            weather: WeatherKind::Clear,
+            seasons: None,
+            bloom: Bloom::None,
+            bloom_density: 0.05,
+            grow_realtime: false,
+            save: None,
+            count: 1,
+            duration: None,
+            easing: Easing::default(),
+            growth_order: GrowthOrder::default(),
+            leaf_reveal: LeafReveal::default(),
+            gradient: true,
+            color: ColorMode::Auto,
+            marker: CanvasMarker::Dot,
+            roots: false,
+            browse: false,
+            favorites: None,
+        }
+    }
+}
 
-    show_particles: bool,
+/// Flips through seeds with `--browse`, one static tree at a time.
+struct Browser {
+    settings: Settings,
+    seed: u64,
+    favorites: Favorites,
 }
 
-impl<'a> App<'a> {
-    fn new(terminal_rect: Rect, theme: u16, show_particles: bool) -> Self {
-        Self {
-            tree_points: Points {
-                coords: &[],
-                color: match theme {
-                    1 => Color::Rgb(205,133,63),
-                    2 => Color::Rgb(144, 128, 90),
-                    3 => Color::Rgb(224, 171, 61),
-                    4 => Color::Rgb(80, 80, 200),
-                    _ => Color::Rgb(205,133,63),
-                }
-            },
-
-            leaf_points: Points {
-                coords: &[],
-                color: match theme {
-                    1 => Color::Green,
-                    2 => Color::Rgb(220, 201, 201),
-                    3 => Color::Rgb(236, 65, 32),
-                    4 => Color::Rgb(173, 216, 230),
-                    _ => Color::Green
-                }
-            },
-
-            pot_points: Points {
-                coords: &[],
-                color: Color::Magenta
-            },
-
-            particles: Points {
-                coords: &[],
-                color: match theme {
-                    1 => Color::LightGreen,
-                    2 => Color::Rgb(239, 169, 228),
-                    3 => Color::Rgb(200, 7, 6),
-                    4 => Color::Rgb(70, 130, 180),
-                    _ => Color::LightGreen,
-                }
-            },
+pub struct App<'a> {
+    plots: Vec <Plot<'a>>,
+
+    // plot that receives keyboard input, the one last clicked
+    active: usize,
 
-            marker: Marker::Dot,
+    color: ColorMode,
 
-            bounds: ((terminal_rect.width-terminal_rect.x) as f64,(terminal_rect.height-terminal_rect.y)as f64),
+    browser: Option<Browser>,
+}
 
-            show_particles,
+impl<'a> App<'a> {
+    /// Lays out `settings.count` plots side by side in `area`, a garden grows a tree from
+    /// its own seed in each of them.
+    pub fn new(settings: &Settings, area: Rect) -> Result<Self, BonsaiError> {
+        let count = u16::max(settings.count, 1);
+        let too_small = BonsaiError::TerminalTooSmall { size: (area.width, area.height), min: MIN_PLOT_SIZE, count };
+
+        // checked before the layout, solving it for thousands of plots takes minutes
+        if (area.width as u32) < MIN_PLOT_SIZE.0 as u32 * count as u32 || area.height < MIN_PLOT_SIZE.1 {
+            return Err(too_small);
         }
-    }
 
-    fn ui(&self, frame: &mut Frame) {
-        let tree = Rect::new(0, 0, frame.size().width, frame.size().height);
-        frame.render_widget(self.tree_canvas(), tree);
+        let areas = Layout::horizontal(vec![Constraint::Ratio(1, count as u32); count as usize]).split(area);
+
+        if areas.iter().any(|area| area.width < MIN_PLOT_SIZE.0 || area.height < MIN_PLOT_SIZE.1) {
+            return Err(too_small);
+        }
+
+        let seed = settings.seed.unwrap_or_else(rand::random);
+
+        Ok(App {
+            plots: areas.iter().enumerate()
+                .map(|(ix, &area)| {
+                    let theme = ((settings.theme as usize + THEMES as usize - 1 + ix) % THEMES as usize) as u16 + 1;
+                    let mut plot = Plot::new(area, settings, theme, garden_seed(seed, ix));
+
+                    if count > 1 {
+                        plot.set_title(format!("Bonsai - seed {}", garden_seed(seed, ix)));
+                    }
+
+                    plot
+                })
+                .collect(),
+
+            active: 0,
+
+            color: settings.color,
+
+            browser: None,
+        })
     }
 
-    fn tree_canvas(&self) -> impl Widget + '_ {
-        Canvas::default()
-            .block(Block::default().borders(Borders::ALL).title("Bonsai"))
-            .marker(self.marker)
-            .paint(|ctx| {
-                ctx.draw(&self.pot_points);
-                ctx.layer();
-                ctx.draw(&self.tree_points);
-                ctx.layer();
-                ctx.draw(&self.leaf_points);
-                ctx.layer();
-                ctx.draw(&self.particles);
-            })
-            .x_bounds([0.0, self.bounds.0])
-            .y_bounds([0.0, self.bounds.1])
+    pub fn ui(&self, frame: &mut Frame) {
+        self.plots.iter().for_each(|plot| plot.render(frame));
+
+        self.color.apply(frame.buffer_mut());
     }
 
+    pub fn run(mut settings: Settings) -> Result<(), BonsaiError> {
+        let save_path = match settings.save.take() {
+            Some(path) => Some(path),
+            None if settings.grow_realtime => Some(saved::realtime_path()?),
+            None => None,
+        };
+
+        let mut saved = save_path.map(|path| SavedTree::load_or_plant(path, &settings)).transpose()?;
+
+        if let Some(saved) = &saved {
+            settings.seed = Some(saved.seed);
+            settings.theme = saved.theme;
+            settings.bloom = saved.bloom;
+            settings.bloom_density = saved.bloom_density;
+        }
+
+        settings.color = settings.color.resolve();
+        settings.gradient = settings.gradient && settings.color.has_gradients();
+
+        let realtime = settings.grow_realtime;
+        if realtime {
+            settings.live = true;
+        }
+
+        // browsed trees are shown grown at once, starting from the seed given if any
+        if settings.browse {
+            settings.live = false;
+            settings.seed.get_or_insert_with(rand::random);
+        }
+
+        let (width, height) = terminal::size()?;
+        let mut app = App::new(&settings, Rect::new(0, 0, width, height))?;
+
+        if settings.browse {
+            let path = match settings.favorites.take() {
+                Some(path) => path,
+                None => saved::favorites_path()?,
+            };
+
+            app.browser = Some(Browser {
+                seed: settings.seed.unwrap_or_default(),
+                favorites: Favorites::load(path)?,
+                settings: settings.clone(),
+            });
+            app.browse(0);
+        }
 
-    pub fn run(seed: Option<u64>, live: bool, theme: u16, particles: bool) -> io::Result<()> {
         let mut terminal = init_terminal()?;
 
-        let terminal_size = terminal.size().unwrap();
-        let mut app = App::new(terminal_size, theme, particles);
+        if let Some(saved) = saved.as_mut() {
+            app.plots[0].apply_edits(&saved.edits);
 
-        let mut tree = BonsaiTree::new(terminal_size, seed, live);
+            if realtime {
+                saved.measure(&plot::grow_tree(app.plots[0].area(), &settings, saved.seed));
+                app.plots[0].set_title(format!("Bonsai - day {}", saved.age_days() + 1));
+            }
+        }
 
-        tree.generate();
-        tree.normalize();
+        let result = app.event_loop(&mut terminal, &mut saved, realtime);
 
-        app.pot_points.coords = Box::leak(tree.get_pot().into_boxed_slice());
+        restore_terminal()?;
+        result?;
 
-        if !live {
-            app.tree_points.coords = Box::leak(tree.get_tree().into_boxed_slice());
+        if let Some(saved) = saved.as_mut() {
+            saved.edits = app.plots[0].edits().to_vec();
+            saved.save()?;
+        }
+
+        Ok(())
+    }
 
-            app.leaf_points.coords = Box::leak(tree.get_leaves().into_boxed_slice());
+    /// Moves every plot on by the time `elapsed` since the previous tick, a saved real time
+    /// tree is caught up to the wall clock in the first plot instead.
+    pub fn tick(&mut self, elapsed: Duration, mut saved: Option<&mut SavedTree>) {
+        for (ix, plot) in self.plots.iter_mut().enumerate() {
+            plot.advance(elapsed, if ix == 0 { saved.take() } else { None });
         }
+    }
+
+    /// Shows the tree `step` seeds after the one browsed so far.
+    fn browse(&mut self, step: i64) {
+        let Some(browser) = self.browser.as_mut() else { return };
+
+        browser.seed = browser.seed.wrapping_add_signed(step);
+
+        let mut plot = Plot::new(self.plots[0].area(), &browser.settings, browser.settings.theme, browser.seed);
+        plot.keep_view(&self.plots[0]);
+
+        let star = if browser.favorites.contains(browser.seed) { " ★" } else { "" };
+        plot.set_title(format!("Bonsai - seed {}{}", browser.seed, star));
 
+        self.plots[0] = plot;
+    }
+
+    fn toggle_favorite(&mut self) -> io::Result<()> {
+        if let Some(browser) = self.browser.as_mut() {
+            browser.favorites.toggle(browser.seed)?;
+            self.browse(0);
+        }
+
+        Ok(())
+    }
+
+    fn event_loop(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>, saved: &mut Option<SavedTree>, realtime: bool) -> io::Result<()> {
         let mut last_tick = Instant::now();
         let tick_rate = Duration::from_millis(TICK_RATE);
 
+        // quitting first plays the growth backwards, a second 'q' skips it
+        let mut withering = false;
+
         loop {
-            let _ = terminal.draw(|frame| app.ui(frame));
+            terminal.draw(|frame| self.ui(frame))?;
             let timeout = tick_rate.saturating_sub(last_tick.elapsed());
             if event::poll(timeout)? {
-                if let Event::Key(key) = event::read()? {
-                    match key.code {
-                        KeyCode::Char('q') => break,
+                let plot = &mut self.plots[self.active];
+
+                match event::read()? {
+                    Event::Key(key) => match key.code {
+                        KeyCode::Char('q') if withering => break,
+                        KeyCode::Char('q') => withering = true,
+                        KeyCode::Char('w') => self.plots.iter_mut().for_each(Plot::cycle_weather),
+                        KeyCode::Char('+') => self.plots.iter_mut().for_each(Plot::intensify_weather),
+                        KeyCode::Char('-') => self.plots.iter_mut().for_each(Plot::calm_weather),
+                        KeyCode::Char('m') => self.plots.iter_mut().for_each(Plot::cycle_marker),
+                        KeyCode::Char('u') => plot.undo(),
+                        KeyCode::Tab => plot.toggle_wiring(),
+                        KeyCode::Char(' ') => self.plots.iter_mut().for_each(Plot::toggle_pause),
+                        KeyCode::Char('s') => {
+                            if let Some(saved) = saved.as_mut() {
+                                saved.edits = self.plots[0].edits().to_vec();
+                                saved.save()?;
+                            }
+                        }
+                        KeyCode::Left if self.browser.is_some() => self.browse(-1),
+                        KeyCode::Right if self.browser.is_some() => self.browse(1),
+                        KeyCode::Enter if self.browser.is_some() => self.toggle_favorite()?,
+                        // the keys above work in wire mode too, the rest move the selection or wire it
+                        code if plot.is_wiring() => plot.on_wiring_key(code),
+                        // the real time tree follows the wall clock, its growth can't be moved
+                        KeyCode::Left if !realtime => self.plots.iter_mut().for_each(|plot| plot.scrub(-1)),
+                        KeyCode::Right if !realtime => self.plots.iter_mut().for_each(|plot| plot.scrub(1)),
                         _ => {}
+                    },
+                    Event::Mouse(mouse) => {
+                        // only a click moves the keyboard to another plot, not the pointer passing over it
+                        if let MouseEventKind::Down(_) = mouse.kind {
+                            if let Some(ix) = self.plots.iter().position(|plot| plot.contains(mouse.column, mouse.row)) {
+                                self.active = ix;
+                            }
+                        }
+
+                        let plot = &mut self.plots[self.active];
+
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) if plot.is_wiring() => {
+                                plot.select_at(mouse.column, mouse.row);
+                            }
+                            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) if !plot.is_wiring() => {
+                                plot.prune_at(mouse.column, mouse.row);
+                            }
+                            _ => {}
+                        }
                     }
+                    _ => {}
                 }
             }
             if last_tick.elapsed() >= tick_rate {
-                app.on_tick(&mut tree, live);
+                let elapsed = last_tick.elapsed();
                 last_tick = Instant::now();
-            }
-        }
-
 
-        restore_terminal()
-
-    }
-    fn on_tick(&mut self, tree: &mut BonsaiTree, live: bool) {
-        let all_changes = tree.animation_step();
+                if withering && self.plots.iter_mut().map(Plot::wither).filter(|&left| left).count() == 0 {
+                    break;
+                }
 
-        if live {
-            self.tree_points.coords = process(self.tree_points.coords, &all_changes, PointType::filter_tree);
+                self.tick(elapsed, saved.as_mut().filter(|_| realtime));
 
-            self.leaf_points.coords = process(self.leaf_points.coords, &all_changes, PointType::filter_leaf);
+                if let Some(saved) = saved.as_mut().filter(|_| realtime) {
+                    saved.save_progress()?;
+                }
+            }
         }
 
-        if self.show_particles {
-            self.particles.coords = Box::leak(filter(&all_changes, PointType::filter_particles).into_boxed_slice());
-        }
+        Ok(())
     }
 }
+
+/// Seed of the tree in the garden's plot `ix`, the first plot grows the tree the seed alone would.
+fn garden_seed(seed: u64, ix: usize) -> u64 {
+    seed ^ (ix as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
 fn init_terminal() -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
+    // a panic would otherwise leave the terminal in raw mode on the alternate screen
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        hook(info);
+    }));
+
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableMouseCapture)?;
     Terminal::new(CrosstermBackend::new(stdout()))
 }
 fn restore_terminal() -> io::Result<()> {
     disable_raw_mode()?;
+    stdout().execute(DisableMouseCapture)?;
     stdout().execute(LeaveAlternateScreen)?;
     Ok(())
 }
-
-fn process<'a, 'b, F>(coords: &'a [(f64, f64)], changes: &'b Vec <PointType>, f: F) -> &'a [(f64, f64)] 
-    where F: FnMut(&PointType) -> Option<(f64, f64)> {
-        let new_coords = filter(changes, f);
-
-        Box::leak([coords, Box::leak(new_coords.into_boxed_slice())].concat().into_boxed_slice())
-}
-
-fn filter<F>(changes: &Vec <PointType>, f: F) -> Vec <(f64, f64)> 
-    where F: FnMut(&PointType) -> Option<(f64, f64)> {
-        changes.iter()
-            .filter_map(f)
-            .collect()
-}
@@ -1,17 +1,30 @@
+pub mod growth;
+pub mod particles;
+pub mod seasons;
+pub mod seed;
 pub mod utils;
 
 use std::cmp::Ordering;
+use std::collections::HashSet;
 
 use rand;
-use rand::rngs::StdRng;
-use rand::{Rng, SeedableRng};
+use rand::seq::SliceRandom;
+use rand::Rng;
 use ratatui::layout::Rect;
+use serde::{Deserialize, Serialize};
 
+use growth::{GrowthOrder, GrowthStyle, LeafReveal};
+use particles::{Ground, Particle, MAX_PARTICLES};
+use seasons::{Season, SeasonClock};
+use seed::TreeRng;
 use utils::Point;
 
 const ANIMATION_STEP: i32 = 100;
 const DT: f64 = 0.001;
 
+// points drawn per dot the canvas can show along a line, a little more so diagonals have no gaps
+const OVERSAMPLING: f64 = 1.5;
+
 const Y_GROWTH: i32 = 4;
 const MAX_X_GROWTH: i32 = 1;
 const BRANCHES_TIERS: i32 = 2;
@@ -19,20 +32,59 @@ const BRANCH_COOLDOWN: i32 = 1;
 
 const POT_HEIGHT: f64 = 1.0 / 7.0;
 
+// tree bounds of a 120x40 plot, leaf clusters are drawn at their generated size there
+// and scaled along with the rest of the tree anywhere else
+const REFERENCE_BOUNDS: (f64, f64) = (120.0, 34.0);
+
+const MAX_DEBRIS: usize = 200;
+
+const MAX_ROOTS_PER_SIDE: usize = 2;
+
+#[derive(Clone)]
 enum AnimationItem {
+    Roots(f64),
     Start,
     Tree(usize, usize, f64),
     Leaf(usize, usize),
-    Particle(Point),
+    Bloom(usize, usize),
+    Particle(Particle),
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum PointType {
     Tree(Point),
     Leaf(Point),
+    Bloom(Point),
     Particle(Point),
+    Fading(Point),
+    Debris(Point),
+}
+
+/// Change made to a generated tree by the user, replayed when a saved tree is loaded.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    /// Cuts the branch leading to the node, removing the whole subtree.
+    Cut(usize),
+    /// Rotates the node and its subtree around the node's parent, angle in radians.
+    Rotate { node: usize, angle: f64 },
+    /// Rotates every joint of the subtree by the angle, curving the branch.
+    Bend { node: usize, angle: f64 },
+}
+
+impl Edit {
+    fn node(&self) -> usize {
+        match *self {
+            Edit::Cut(node) | Edit::Rotate { node, .. } | Edit::Bend { node, .. } => node,
+        }
+    }
 }
 
 impl PointType {
+    /// Particles move every step, so only their latest position is worth drawing.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, PointType::Particle(_) | PointType::Fading(_) | PointType::Debris(_))
+    }
+
     pub fn filter_tree(val: &PointType) -> Option<(f64, f64)> {
         match val {
             PointType::Tree(p) => Some((p.x, p.y)),
@@ -47,56 +99,155 @@ impl PointType {
         }
     }
     
+    pub fn filter_bloom(val: &PointType) -> Option<(f64, f64)> {
+        match val {
+            PointType::Bloom(p) => Some((p.x, p.y)),
+            _ => None
+        }
+    }
+
     pub fn filter_particles(val: &PointType) -> Option<(f64, f64)> {
         match val {
             PointType::Particle(p) => Some((p.x, p.y)),
             _ => None
         }
     }
+
+    pub fn filter_fading(val: &PointType) -> Option<(f64, f64)> {
+        match val {
+            PointType::Fading(p) => Some((p.x, p.y)),
+            _ => None
+        }
+    }
+
+    pub fn filter_debris(val: &PointType) -> Option<(f64, f64)> {
+        match val {
+            PointType::Debris(p) => Some((p.x, p.y)),
+            _ => None
+        }
+    }
+}
+
+/// What of the tree has grown so far, used to find what a step of the animation added.
+#[derive(Clone)]
+pub struct Growth {
+    roots: f64,
+    edges: Vec <f64>,
+    leaves: Vec <usize>,
+    blooms: Vec <usize>,
 }
 
+/// State of the growth after some step, enough to continue the animation from there.
+#[derive(Clone)]
+struct Keyframe {
+    growth: Growth,
+    queue: Vec <AnimationItem>,
+    ctr: Option<usize>,
+}
+
+#[derive(Clone)]
 pub struct BonsaiTree {
     nodes: Vec <Point>,
+    parents: Vec <usize>,
+    // tier of the branch every node is on, the trunk has the most and branches of a tier grow from the one above
+    tiers: Vec <u32>,
+    // first node of the trunk and of every branch growing from another one
+    branch_starts: Vec <usize>,
+    // how far the branch leading to every node has grown, from 0 to 1
+    edge_progress: Vec <f64>,
+    // circles the leaves of every node are made of, in the units of a reference sized plot
     leaves_preprocess: Vec <Vec <(Point, i32)>>,
+    leaf_scale: (f64, f64),
     leaves: Vec <Vec <Point>>,
 
+    // surface roots, lines from the base of the trunk along the top of the pot and over its rim
+    roots: Vec <Vec <Point>>,
+    // how far all roots have grown, from 0 to 1
+    roots_progress: f64,
+    grow_roots: bool,
+
     leaves_flat: Vec <Point>,
+    leaves_offset: Vec <usize>,
+    // how many leaves of every node, in growth order, are currently on the tree
+    leaves_shown: Vec <usize>,
+
+    // flowers or fruit on the outline of leaf clusters, relative to their node like leaves
+    blooms: Vec <Vec <Point>>,
+    blooms_shown: Vec <usize>,
+    bloom_density: f64,
+
+    seasons: Option<SeasonClock>,
+
+    style: GrowthStyle,
 
     particle_direction: Point,
 
     pot: [Point; 4],
+    ground: Ground,
 
     tree_bounds: (u16, u16),
     bounds: (u16, u16),
 
-    rng: StdRng,
+    rng: TreeRng,
 
     neighbours: Vec <Vec <usize>>,
+
+    // dots the canvas shows per unit of the bounds, horizontally and vertically
+    resolution: (f64, f64),
+
+    edits: Vec <Edit>,
+    pruned: Vec <bool>,
+
     animation_queue: Vec <AnimationItem>,
     animation_ctr: Option<usize>,
+    next_particle_id: u64,
+
+    // every step of the growth, so it can be played from any point in both directions
+    timeline: Vec <Keyframe>,
+    step: usize,
 }
 
 impl BonsaiTree {
-    pub fn new(bounds: Rect, seed: Option <u64>, live: bool) -> Self {
-        let tree_bounds = (bounds.width - bounds.x, f64::floor((1.0 - POT_HEIGHT) *(bounds.height - bounds.y) as f64) as u16);
-        let bounds = (bounds.width - bounds.x , bounds.height - bounds.y);
+    pub fn new(bounds: Rect, seed: Option <u64>, live: bool, year: Option<u32>, bloom_density: f64, style: GrowthStyle) -> Self {
+        let tree_bounds = (bounds.width.saturating_sub(bounds.x), f64::floor((1.0 - POT_HEIGHT) * bounds.height.saturating_sub(bounds.y) as f64) as u16);
+        let bounds = (bounds.width.saturating_sub(bounds.x), bounds.height.saturating_sub(bounds.y));
 
         let pot = get_pot_points(bounds);
 
-        let mut rng = if let Some(s) = seed { StdRng::seed_from_u64(s) } else { StdRng::from_entropy() };
+        let mut rng = seed::rng(seed.unwrap_or_else(rand::random));
 
-        let particle_direction = Point::from_phi(rng.gen::<f64>() % 3.14) * -1.0;
+        let particle_direction = Point::from_phi(rng.gen::<f64>() % std::f64::consts::PI) * -1.0;
 
         BonsaiTree {
             nodes: Vec::new(),
+            parents: Vec::new(),
+            tiers: Vec::new(),
+            branch_starts: Vec::new(),
+            edge_progress: Vec::new(),
 
             leaves_preprocess: Vec::new(),
+            leaf_scale: (1.0, 1.0),
             leaves: Vec::new(),
             leaves_flat: Vec::new(),
+            leaves_offset: Vec::new(),
+            leaves_shown: Vec::new(),
+
+            roots: Vec::new(),
+            roots_progress: if live { 0.0 } else { 1.0 },
+            grow_roots: false,
+
+            blooms: Vec::new(),
+            blooms_shown: Vec::new(),
+            bloom_density,
+
+            seasons: year.map(SeasonClock::new),
+
+            style,
 
             particle_direction,
 
             pot,
+            ground: Ground::new(pot, bounds.0),
 
             bounds,
             tree_bounds,
@@ -104,16 +255,33 @@ impl BonsaiTree {
             rng,
 
             neighbours: Vec::new(),
-            animation_queue: vec![AnimationItem::Start],
+
+            resolution: (1.0, 1.0),
+
+            edits: Vec::new(),
+            pruned: Vec::new(),
+
+            animation_queue: if live { vec![AnimationItem::Start] } else { Vec::new() },
             animation_ctr: if live { Some(1) } else { None },
+            next_particle_id: 0,
+
+            timeline: Vec::new(),
+            step: 0,
         }
     }
 
-    fn push(&mut self, p: &Point, parent: usize) -> usize {
+    fn push(&mut self, p: &Point, parent: usize, tier: i32) -> usize {
         self.nodes.push(*p);
+        self.parents.push(parent);
+        self.tiers.push(tier as u32);
+        self.edge_progress.push(if self.animation_ctr.is_none() { 1.0 } else { 0.0 });
+        self.pruned.push(false);
 
         self.neighbours.push(Vec::new());
         self.leaves.push(Vec::new());
+        self.leaves_shown.push(0);
+        self.blooms.push(Vec::new());
+        self.blooms_shown.push(0);
         self.leaves_preprocess.push(Vec::new());
         if self.nodes.len() > 1 {
             self.neighbours[parent].push(self.nodes.len() - 1);
@@ -142,15 +310,165 @@ impl BonsaiTree {
             *v = *v + Point::from_floats(0.0, offset_y as f64);
         });
 
+        // leaves are only laid out on the cells now that the size of the tree is known
+        self.leaf_scale = (self.tree_bounds.0 as f64 / REFERENCE_BOUNDS.0, self.tree_bounds.1 as f64 / REFERENCE_BOUNDS.1);
+        (0..self.nodes.len()).for_each(|ix| self.process_leaves(ix));
+
+        self.generate_blooms();
+
+        self.order_leaves();
+
+        self.generate_roots();
+
         self.get_leaves_flat();
+
+        self.record_timeline();
+    }
+
+    /// Grows surface roots over the rim of the pot, has to be set before [`BonsaiTree::normalize`].
+    pub fn set_roots(&mut self, roots: bool) {
+        self.grow_roots = roots;
     }
 
+    /// Generates the shape of the tree, the same for a seed whatever the size of the plot,
+    /// [`BonsaiTree::normalize`] then fits it into the bounds.
     pub fn generate(&mut self) {
+        self.generate_tiers(BRANCHES_TIERS as u32);
+    }
+
+    /// Like [`BonsaiTree::generate`] with `tiers` levels of branches growing from branches,
+    /// every tier roughly doubles the size of the tree.
+    pub fn generate_tiers(&mut self, tiers: u32) {
         let xdir = if self.rng.gen::<i32>() % 2 == 0 { -1 } else { 1 };
 
-        self.generate_tree(Point::from_floats(0.0, 0.0), Y_GROWTH, BRANCHES_TIERS, xdir, 0);
+        self.branch_starts.push(0);
+        self.generate_tree(Point::from_floats(0.0, 0.0), Y_GROWTH, u32::max(tiers, 1) as i32, xdir, 0);
+    }
+
+    fn generate_blooms(&mut self) {
+        if self.bloom_density <= 0.0 {
+            return;
+        }
+
+        // separate generator so that enabling blooms doesn't change the tree grown from a seed
+        let mut rng = seed::rng(self.rng.gen());
+        let density = f64::min(self.bloom_density, 1.0);
+
+        for ix in 0..self.nodes.len() {
+            let cells: HashSet<(i64, i64)> = self.leaves[ix].iter()
+                .map(|p| (p.x.round() as i64, p.y.round() as i64))
+                .collect();
+
+            let is_edge = |p: &Point| {
+                let (x, y) = (p.x.round() as i64, p.y.round() as i64);
+                [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)].iter().any(|c| !cells.contains(c))
+            };
+
+            self.blooms[ix] = self.leaves[ix].iter()
+                .filter(|p| is_edge(p))
+                .filter(|_| rng.gen_bool(density))
+                .copied()
+                .collect();
+
+            if self.animation_ctr.is_none() {
+                self.blooms_shown[ix] = self.blooms[ix].len();
+            }
+        }
+    }
+
+    // the roots have their own generator and come after everything else, so they don't change the tree either
+    fn generate_roots(&mut self) {
+        self.roots.clear();
+        if !self.grow_roots || self.nodes.is_empty() {
+            return;
+        }
+
+        let mut rng = seed::rng(self.rng.gen());
+
+        let base = self.nodes[0];
+        // the top of the pot runs from its fourth corner to its third
+        let (left, right, rim) = (self.pot[3].x, self.pot[2].x, self.pot[3].y);
+        let half_width = (right - left) / 2.0;
+
+        for direction in [-1.0, 1.0] {
+            for _ in 0..rng.gen_range(1..=MAX_ROOTS_PER_SIDE) {
+                let reach = half_width * rng.gen_range(0.5..1.15);
+                let end = base.x + direction * reach;
+                let edge = if direction < 0.0 { left } else { right };
+
+                // roots flare out of the trunk a little above the soil before they lie on it
+                let mut root = vec![base, Point::from_floats(base.x + direction * reach * 0.3, rim + rng.gen_range(0.2..0.8))];
+
+                let over = direction * (end - edge);
+                if over > 0.0 {
+                    root.push(Point::from_floats(edge, rim));
+                    // hanging down at most half the side of the pot
+                    root.push(Point::from_floats(end, rim - f64::min(over * 0.5, rim * 0.5)));
+                } else {
+                    root.push(Point::from_floats(end, rim));
+                }
+
+                self.roots.push(root);
+            }
+        }
+
+        if self.animation_ctr.is_some() {
+            self.animation_queue = vec![self.first_item()];
+        }
+    }
+
+    // the roots grow first if there are any, then the trunk
+    fn first_item(&self) -> AnimationItem {
+        if self.roots.is_empty() { AnimationItem::Start } else { AnimationItem::Roots(0.0) }
+    }
+
+    // runs after everything else is generated, the order leaves are revealed in doesn't change the tree
+    fn order_leaves(&mut self) {
+        match self.style.reveal {
+            LeafReveal::Radial => (),
+            LeafReveal::Sparkle => {
+                let mut rng = seed::rng(self.rng.gen());
+                self.leaves.iter_mut().for_each(|leaves| leaves.shuffle(&mut rng));
+            }
+            LeafReveal::TopDown => self.leaves.iter_mut().for_each(|leaves| leaves.sort_by(|a, b| b.y.total_cmp(&a.y))),
+        }
+    }
+
+    // the growth doesn't use the random generator, so a dry run gives every frame of it
+    fn record_timeline(&mut self) {
+        let mut scratch = self.clone();
+
+        scratch.roots_progress = 0.0;
+        scratch.edge_progress.iter_mut().for_each(|p| *p = 0.0);
+        scratch.leaves_shown.iter_mut().for_each(|shown| *shown = 0);
+        scratch.blooms_shown.iter_mut().for_each(|shown| *shown = 0);
+        scratch.animation_queue = vec![scratch.first_item()];
+        scratch.animation_ctr = Some(1);
+        // the seasons would regrow leaves every spring and keep the dry run from ever ending
+        scratch.seasons = None;
+
+        let mut timeline = vec![scratch.keyframe()];
+        while !scratch.is_grown() {
+            scratch.animation_step();
+            timeline.push(scratch.keyframe());
+        }
+
+        self.step = if self.animation_ctr.is_some() { 0 } else { timeline.len() - 1 };
+        self.timeline = timeline;
+    }
+
+    fn keyframe(&self) -> Keyframe {
+        Keyframe {
+            growth: self.growth(),
+            queue: self.animation_queue.iter()
+                .filter(|item| !matches!(item, AnimationItem::Particle(_)))
+                .cloned()
+                .collect(),
+            ctr: self.animation_ctr,
+        }
     }
 
+    #[allow(clippy::modulo_one)]
     fn generate_tree(&mut self, pos: Point, growth: i32, tier: i32, xdir: i32, mut parent: usize) {
         if tier == 0 {
             self.generate_leaves(Point::from_floats(0.0, 0.0), 3, false, parent);
@@ -168,7 +486,7 @@ impl BonsaiTree {
             let grow_y = 0.1 * self.rng.gen_range(1..10) as f64;
             next_pos = next_pos + Point::from_floats(xdir as f64, grow_y);
 
-            parent = self.push(&next_pos, parent);
+            parent = self.push(&next_pos, parent, tier);
         }
 
         if growth % BRANCH_COOLDOWN == 0 && self.rng.gen::<i32>() % tier == 0 {
@@ -183,6 +501,11 @@ impl BonsaiTree {
 
         let next_dir = xdir * r;
 
+        // the last tier only carries leaves
+        if tier > 1 {
+            self.branch_starts.push(self.nodes.len());
+        }
+
         self.generate_tree(pos, 1 << (tier - 1), tier - 1, next_dir, parent);
     }
 
@@ -201,8 +524,6 @@ impl BonsaiTree {
 
             let dir_y = self.rng.gen::<f64>() % 4.0;
             self.generate_leaves(pos + Point::from_floats(-circle_radius as f64, dir_y), depth - 1, false, parent);
-
-            self.process_leaves(parent);
         } else {
             let dir_y = self.rng.gen::<f64>() % 4.0;
 
@@ -217,11 +538,26 @@ impl BonsaiTree {
     }
 
     fn process_leaves(&mut self, parent: usize) {
+        let (scale_x, scale_y) = self.leaf_scale;
+
+        // leaves past the edges of the plot couldn't be drawn, clusters there are cut off
+        let node = self.nodes[parent];
+        let inside = |p: Point| (0.0..=self.bounds.0 as f64).contains(&p.x) && (0.0..=self.bounds.1 as f64).contains(&p.y);
+
         for &(center, radius) in self.leaves_preprocess[parent].iter() {
-            for x in -radius..=radius {
-                for y in -radius..radius {
-                    if center.y + y as f64 >= 0.0 && x*x + y*y <= radius*radius {
-                        self.leaves[parent].push(center + Point::from_floats(x as f64, y as f64));
+            let center = Point::from_floats(center.x * scale_x, center.y * scale_y);
+            let (radius_x, radius_y) = (radius as f64 * scale_x, radius as f64 * scale_y);
+
+            let (reach_x, reach_y) = (f64::ceil(radius_x) as i32, f64::ceil(radius_y) as i32);
+
+            for x in -reach_x..=reach_x {
+                for y in -reach_y..reach_y {
+                    let (dx, dy) = (x as f64 / radius_x, y as f64 / radius_y);
+
+                    let leaf = center + Point::from_floats(x as f64, y as f64);
+
+                    if center.y + y as f64 >= 0.0 && dx * dx + dy * dy <= 1.0 && inside(node + leaf) {
+                        self.leaves[parent].push(leaf);
                     }
                 }
             }
@@ -232,44 +568,183 @@ impl BonsaiTree {
             else if a.norm2() == b.norm2() { Ordering::Equal }
             else { Ordering::Greater }
         );
+
+        if self.animation_ctr.is_none() {
+            self.leaves_shown[parent] = self.leaves[parent].len();
+        }
     }
 
     fn get_leaves_flat(&mut self) {
+        self.leaves_flat.clear();
+        self.leaves_offset.clear();
+
         for (ix, v) in self.leaves.iter().enumerate() {
+            self.leaves_offset.push(self.leaves_flat.len());
             self.leaves_flat.extend(v.iter().map(|&p| self.nodes[ix] + p));
         }
     }
 
+    /// Nodes and leaves the animation grows one by one, whatever the steps it takes for them.
+    pub fn animation_size(&self) -> usize {
+        self.nodes.len() + self.leaves_flat.len()
+    }
+
+    fn growth_finished(&self) -> bool {
+        self.animation_ctr.is_none_or(|x| x == self.animation_size())
+    }
+
+    /// True once nothing is left to grow, only particles remain in the animation.
+    pub fn is_grown(&self) -> bool {
+        self.animation_queue.iter().all(|item| matches!(item, AnimationItem::Particle(_)))
+    }
+
+    /// Animation steps it takes the tree to grow from the pot to its full size.
+    pub fn growth_steps(&self) -> usize {
+        self.timeline.len().saturating_sub(1)
+    }
+
+    /// Step of the growth the tree is currently at, equal to [`BonsaiTree::growth_steps`] once grown.
+    pub fn current_step(&self) -> usize {
+        self.step
+    }
+
+    /// Moves the growth to `step`, backwards or forwards, particles in the air keep falling.
+    pub fn seek(&mut self, step: usize) {
+        let Some(frame) = self.timeline.get(usize::min(step, self.growth_steps())) else { return };
+
+        self.roots_progress = frame.growth.roots;
+        self.edge_progress = frame.growth.edges.clone();
+        self.leaves_shown = frame.growth.leaves.clone();
+        self.blooms_shown = frame.growth.blooms.clone();
+        self.animation_ctr = frame.ctr;
+
+        self.animation_queue.retain(|item| matches!(item, AnimationItem::Particle(_)));
+        self.animation_queue.extend(frame.queue.iter().cloned());
+
+        self.step = usize::min(step, self.growth_steps());
+    }
+
+    pub fn season(&self) -> Option<(Season, f64)> {
+        self.seasons.as_ref().map(|clock| (clock.season(), clock.progress()))
+    }
+
+    fn season_step(&mut self, queue: &mut Vec <AnimationItem>, particle_count: &mut usize) {
+        let Some(clock) = self.seasons.as_mut() else { return };
+
+        let previous = clock.season();
+        clock.tick();
+
+        match (clock.season(), clock.progress()) {
+            // leaves first turn color, then fall during the second half of autumn
+            (Season::Autumn, progress) if progress >= 0.5 => self.shed_leaves(2.0 * (progress - 0.5), queue, particle_count),
+            (Season::Winter, _) => self.shed_leaves(1.0, queue, particle_count),
+            (Season::Spring, _) if previous != Season::Spring => {
+                for ix in 0..self.nodes.len() {
+                    if !self.leaves[ix].is_empty() {
+                        queue.push(AnimationItem::Leaf(ix, 0));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn shed_leaves(&mut self, fraction: f64, queue: &mut Vec <AnimationItem>, particle_count: &mut usize) {
+        for ix in 0..self.nodes.len() {
+            let target = f64::ceil(self.leaves[ix].len() as f64 * (1.0 - fraction)) as usize;
+
+            if target < self.leaves[ix].len() {
+                self.blooms_shown[ix] = 0;
+            }
+
+            while self.leaves_shown[ix] > target {
+                self.leaves_shown[ix] -= 1;
+
+                if *particle_count < MAX_PARTICLES && !self.pruned[ix] {
+                    let p = self.nodes[ix] + self.leaves[ix][self.leaves_shown[ix]];
+                    queue.push(self.spawn(p, false));
+
+                    *particle_count += 1;
+                }
+            }
+        }
+    }
+
     pub fn animation_step(&mut self) -> Vec<PointType> {
         let mut result: Vec<PointType> = Vec::new();
 
         let mut next_frame_queue: Vec <AnimationItem> = Vec::new();
 
-        let animation_size = self.nodes.len() + self.leaves_flat.len();
-        match self.animation_ctr {
-            Some(x) if x != animation_size => (),
-            _ => next_frame_queue.push(self.new_particle()),
+        let mut particle_count = self.animation_queue.iter()
+            .filter(|item| matches!(item, AnimationItem::Particle(_)))
+            .count();
+
+        let growing = !self.growth_finished();
+        let mut branch_finished = false;
+        let mut cluster_finished = false;
+
+        // leaves regrowing in spring don't move the growth on, the timeline ends with the grown tree
+        let grown = self.is_grown();
+        if !grown && self.step < self.growth_steps() {
+            self.step += 1;
+        }
+
+        // the year only starts once the blooms are out too, and pauses while leaves regrow
+        if grown {
+            self.season_step(&mut next_frame_queue, &mut particle_count);
+        }
+
+        if !growing && particle_count < MAX_PARTICLES {
+            next_frame_queue.extend(self.new_particle());
         }
 
         for item in &self.animation_queue {
             match item {
-                AnimationItem::Start => self.neighbours[0].iter().for_each(|&v| next_frame_queue.push(AnimationItem::Tree(0, v, 0.0))),
-                &AnimationItem::Tree(parent, ix, dt) => {
-                    for step in 0..ANIMATION_STEP {
-                        let t = dt + step as f64 * DT;
+                &AnimationItem::Roots(dt) => {
+                    let next_dt = dt + ANIMATION_STEP as f64 * DT;
+                    let finished = f64::abs(1.0 - next_dt) <= 0.001;
 
-                        let p = utils::linear_interpolate(&self.nodes[parent as usize], &self.nodes[ix], t);
+                    let from = self.style.easing.apply(dt);
+                    let to = if finished { 1.0 } else { self.style.easing.apply(next_dt) };
 
-                        result.push(PointType::Tree(p));
+                    if to > from {
+                        for (start, end) in self.root_segments(from, to) {
+                            result.extend(self.sample(start, end).map(PointType::Tree));
+                        }
                     }
 
+                    self.roots_progress = to;
+                    next_frame_queue.push(if finished { AnimationItem::Start } else { AnimationItem::Roots(next_dt) });
+                }
+                AnimationItem::Start => match self.style.order {
+                    GrowthOrder::All => (1..self.nodes.len()).for_each(|v| next_frame_queue.push(AnimationItem::Tree(self.parents[v], v, 0.0))),
+                    _ => self.neighbours[0].iter().for_each(|&v| next_frame_queue.push(AnimationItem::Tree(0, v, 0.0))),
+                },
+                &AnimationItem::Tree(parent, ix, dt) => {
                     let next_dt = dt + ANIMATION_STEP as f64 * DT;
-                    if f64::abs(1.0 - next_dt) <= 0.001 {
+                    let finished = f64::abs(1.0 - next_dt) <= 0.001;
+
+                    // dt moves linearly, the easing decides how much of the branch that is
+                    let from = self.style.easing.apply(dt);
+                    let to = if finished { 1.0 } else { self.style.easing.apply(next_dt) };
+
+                    if !self.pruned[ix] && to > from {
+                        let start = utils::linear_interpolate(&self.nodes[parent], &self.nodes[ix], from);
+                        let end = utils::linear_interpolate(&self.nodes[parent], &self.nodes[ix], to);
+
+                        result.extend(self.sample(start, end).map(PointType::Tree));
+                    }
+
+                    self.edge_progress[ix] = to;
+                    if finished {
                         self.animation_ctr = self.animation_ctr.map(|v| v + 1);
+                        branch_finished = true;
 
-                        self.neighbours[ix].iter().for_each(|&v| next_frame_queue.push(AnimationItem::Tree(ix, v, 0.0)));
+                        if self.style.order == GrowthOrder::Tiered || self.style.order == GrowthOrder::TrunkFirst {
+                            self.neighbours[ix].iter().for_each(|&v| next_frame_queue.push(AnimationItem::Tree(ix, v, 0.0)));
+                        }
 
-                        if !self.leaves[ix].is_empty() {
+                        if self.style.order != GrowthOrder::TrunkFirst && !self.leaves[ix].is_empty() {
                             next_frame_queue.push(AnimationItem::Leaf(ix, 0));
                         }
                     } else {
@@ -279,45 +754,349 @@ impl BonsaiTree {
 
                 &AnimationItem::Leaf(parent, ix) => {
                     let range_end = usize::min(ix + 10, self.leaves[parent].len());
-                    result.extend(self.leaves[parent][ix..range_end].iter().map(|&p| PointType::Leaf(self.nodes[parent] + p)));
+                    if !self.pruned[parent] {
+                        result.extend(self.leaves[parent][ix..range_end].iter().map(|&p| PointType::Leaf(self.nodes[parent] + p)));
+                    }
+
+                    self.leaves_shown[parent] = range_end;
 
                     if ix + 10 < self.leaves[parent].len() {
                         next_frame_queue.push(AnimationItem::Leaf(parent, ix + 10))
                     } else {
-                        self.animation_ctr = self.animation_ctr.map(|v| v + self.leaves[parent].len());
+                        if growing {
+                            self.animation_ctr = self.animation_ctr.map(|v| v + self.leaves[parent].len());
+                        }
+
+                        cluster_finished = true;
                     }
                 }
 
-                &AnimationItem::Particle(mut p) => {
-                    p = p + self.particle_direction;
-                    p = p + Point::from_floats(-1.0 + (self.rng.gen::<i32>() % 3) as f64, 0.0);
+                &AnimationItem::Bloom(parent, ix) => {
+                    if !self.pruned[parent] {
+                        result.push(PointType::Bloom(self.nodes[parent] + self.blooms[parent][ix]));
+                    }
+
+                    self.blooms_shown[parent] = ix + 1;
+
+                    if ix + 1 < self.blooms[parent].len() {
+                        next_frame_queue.push(AnimationItem::Bloom(parent, ix + 1));
+                    }
+                }
 
-                    result.push(PointType::Particle(p));
-                    if p.y > 0.0 {
+                &AnimationItem::Particle(mut p) => {
+                    if let Some(point) = p.step(self.particle_direction, &mut self.ground) {
+                        result.push(point);
                         next_frame_queue.push(AnimationItem::Particle(p));
                     }
                 }
             }
         }
 
+        // the canopy starts in the step the last branch finished
+        let branches_left = next_frame_queue.iter().any(|item| matches!(item, AnimationItem::Roots(_) | AnimationItem::Start | AnimationItem::Tree(..)));
+        if self.style.order == GrowthOrder::TrunkFirst && branch_finished && !branches_left {
+            (0..self.nodes.len())
+                .filter(|&ix| !self.leaves[ix].is_empty())
+                .for_each(|ix| next_frame_queue.push(AnimationItem::Leaf(ix, 0)));
+        }
+
+        // blooms open once the whole canopy is out, in the step its last cluster finished
+        let canopy_left = next_frame_queue.iter().any(|item| !matches!(item, AnimationItem::Bloom(..) | AnimationItem::Particle(_)));
+        if (branch_finished || cluster_finished) && !canopy_left {
+            (0..self.nodes.len())
+                .filter(|&ix| !self.blooms[ix].is_empty())
+                .for_each(|ix| next_frame_queue.push(AnimationItem::Bloom(ix, 0)));
+        }
+
         self.animation_queue = next_frame_queue;
 
         result
     }
 
-    fn new_particle(&mut self) -> AnimationItem {
+    /// Moves the particles in the air on without growing the tree, for growth that waits
+    /// on something else than the animation, e.g. the wall clock.
+    pub fn particle_step(&mut self) -> Vec<PointType> {
+        let mut result = Vec::new();
+
+        let queue = std::mem::take(&mut self.animation_queue);
+        self.animation_queue = queue.into_iter()
+            .filter_map(|item| match item {
+                AnimationItem::Particle(mut p) => {
+                    let point = p.step(self.particle_direction, &mut self.ground)?;
+                    result.push(point);
+
+                    Some(AnimationItem::Particle(p))
+                }
+                item => Some(item),
+            })
+            .collect();
+
+        result
+    }
+
+    // leaves already shed for the season are picked but not dropped, so bare trees stop losing leaves
+    fn new_particle(&mut self) -> Option<AnimationItem> {
+        if self.leaves_flat.is_empty() {
+            return None;
+        }
+
         let ix = self.rng.gen::<usize>() % self.leaves_flat.len();
-        AnimationItem::Particle(self.leaves_flat[ix])
+
+        let node = self.leaves_offset.partition_point(|&offset| offset <= ix) - 1;
+        if self.pruned[node] || ix - self.leaves_offset[node] >= self.leaves_shown[node] {
+            return None;
+        }
+
+        Some(self.spawn(self.leaves_flat[ix], false))
+    }
+
+    fn spawn(&mut self, p: Point, debris: bool) -> AnimationItem {
+        let id = self.next_particle_id;
+        self.next_particle_id += 1;
+
+        if debris {
+            AnimationItem::Particle(Particle::debris(id, p, &mut self.rng))
+        } else {
+            AnimationItem::Particle(Particle::new(id, p, &mut self.rng))
+        }
+    }
+
+    /// Particles currently in the air or on the ground, with ids stable for their whole life.
+    pub fn particles(&self) -> Vec <(u64, PointType)> {
+        self.animation_queue.iter()
+            .filter_map(|item| match item {
+                AnimationItem::Particle(p) => Some((p.id(), p.point())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn growth(&self) -> Growth {
+        Growth {
+            roots: self.roots_progress,
+            edges: self.edge_progress.clone(),
+            leaves: self.leaves_shown.clone(),
+            blooms: self.blooms_shown.clone(),
+        }
+    }
+
+    /// Branch segments, leaves and blooms that grew since `before`, or everything
+    /// visible when `before` is `None`.
+    pub fn grown_since(&self, before: Option<&Growth>) -> (Vec <(Point, Point)>, Vec <Point>, Vec <Point>) {
+        let mut segments = self.root_segments(before.map_or(0.0, |growth| growth.roots), self.roots_progress);
+        let mut leaves = Vec::new();
+        let mut blooms = Vec::new();
+
+        for ix in (0..self.nodes.len()).filter(|&ix| !self.pruned[ix]) {
+            let (edge, leaf, bloom) = match before {
+                Some(growth) => (growth.edges[ix], growth.leaves[ix], growth.blooms[ix]),
+                None => (0.0, 0, 0),
+            };
+
+            if ix != 0 && self.edge_progress[ix] > edge {
+                let parent = &self.nodes[self.parents[ix]];
+                segments.push((
+                    utils::linear_interpolate(parent, &self.nodes[ix], edge),
+                    utils::linear_interpolate(parent, &self.nodes[ix], self.edge_progress[ix]),
+                ));
+            }
+
+            if self.leaves_shown[ix] > leaf {
+                leaves.extend(self.leaves[ix][leaf..self.leaves_shown[ix]].iter().map(|&p| self.nodes[ix] + p));
+            }
+
+            if self.blooms_shown[ix] > bloom {
+                blooms.extend(self.blooms[ix][bloom..self.blooms_shown[ix]].iter().map(|&p| self.nodes[ix] + p));
+            }
+        }
+
+        (segments, leaves, blooms)
+    }
+
+    /// Sets how many dots the canvas shows per unit of the bounds, lines are drawn with
+    /// about one point per dot they cross.
+    pub fn set_resolution(&mut self, x: f64, y: f64) {
+        self.resolution = (f64::max(x, 0.0), f64::max(y, 0.0));
+    }
+
+    /// Points along the line from `start` up to, without, `end`, spaced to the resolution.
+    fn sample(&self, start: Point, end: Point) -> impl Iterator<Item = Point> {
+        let offset = end - start;
+        let dots = f64::hypot(offset.x * self.resolution.0, offset.y * self.resolution.1);
+        let samples = usize::max(1, f64::ceil(dots * OVERSAMPLING) as usize);
+
+        (0..samples).map(move |i| utils::linear_interpolate(&start, &end, i as f64 / samples as f64))
+    }
+
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Child node of the visible branch closest to `p`, if any is within `max_distance`.
+    pub fn nearest_branch(&self, p: Point, max_distance: f64) -> Option<usize> {
+        (1..self.nodes.len())
+            .filter(|&ix| !self.pruned[ix] && self.edge_progress[ix] > 0.0)
+            .map(|ix| {
+                let parent = &self.nodes[self.parents[ix]];
+                let end = utils::linear_interpolate(parent, &self.nodes[ix], self.edge_progress[ix]);
+
+                (ix, utils::segment_distance(&p, parent, &end))
+            })
+            .filter(|&(_, distance)| distance <= max_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(ix, _)| ix)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn parent(&self, node: usize) -> usize {
+        self.parents[node]
+    }
+
+    pub fn children(&self, node: usize) -> &[usize] {
+        &self.neighbours[node]
+    }
+
+    pub fn tier(&self, node: usize) -> u32 {
+        self.tiers[node]
+    }
+
+    /// First nodes of the trunk and of every branch.
+    pub fn branch_starts(&self) -> &[usize] {
+        &self.branch_starts
+    }
+
+    /// The node followed by all of its descendants, parents always before their children.
+    fn subtree(&self, node: usize) -> Vec <usize> {
+        let mut result = vec![node];
+
+        let mut ix = 0;
+        while ix < result.len() {
+            result.extend(self.neighbours[result[ix]].iter().copied());
+            ix += 1;
+        }
+
+        result
+    }
+
+    pub fn apply(&mut self, edit: Edit) {
+        if edit.node() == 0 || edit.node() >= self.nodes.len() {
+            return;
+        }
+
+        // repeated rotations of one node share the pivot, so they add up into a single edit
+        match (self.edits.last_mut(), edit) {
+            (Some(Edit::Rotate { node: last, angle: total }), Edit::Rotate { node, angle }) if *last == node => *total += angle,
+            _ => self.edits.push(edit),
+        }
+
+        match edit {
+            Edit::Cut(_) => self.update_pruned(),
+            Edit::Rotate { node, angle } => self.rotate(node, angle),
+            Edit::Bend { node, angle } => self.subtree(node).into_iter().for_each(|ix| self.rotate(ix, angle)),
+        }
+    }
+
+    /// Cuts the branch leading to `node`, the removed pieces fall down as debris.
+    pub fn prune(&mut self, node: usize) {
+        let before = self.pruned.clone();
+
+        self.apply(Edit::Cut(node));
+
+        let mut debris: Vec <Point> = Vec::new();
+        for ix in (0..self.nodes.len()).filter(|&ix| self.pruned[ix] && !before[ix]) {
+            let parent = &self.nodes[self.parents[ix]];
+            let end = utils::linear_interpolate(parent, &self.nodes[ix], self.edge_progress[ix]);
+            let samples = usize::max(1, f64::sqrt((end - *parent).norm2()) as usize);
+
+            debris.extend((0..samples).map(|i| utils::linear_interpolate(parent, &end, i as f64 / samples as f64)));
+            debris.extend(self.leaves[ix][..self.leaves_shown[ix]].iter().map(|&p| self.nodes[ix] + p));
+        }
+
+        let stride = usize::max(1, debris.len().div_ceil(MAX_DEBRIS));
+        for &p in debris.iter().step_by(stride) {
+            let particle = self.spawn(p, true);
+            self.animation_queue.push(particle);
+        }
+    }
+
+    /// Reverts the last edit, returns false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.edits.pop() else { return false };
+
+        match edit {
+            Edit::Cut(_) => self.update_pruned(),
+            Edit::Rotate { node, angle } => self.rotate(node, -angle),
+            Edit::Bend { node, angle } => self.subtree(node).into_iter().rev().for_each(|ix| self.rotate(ix, -angle)),
+        }
+
+        true
+    }
+
+    fn rotate(&mut self, node: usize, angle: f64) {
+        let pivot = self.nodes[self.parents[node]];
+
+        for ix in self.subtree(node) {
+            self.nodes[ix] = self.nodes[ix].rotate(&pivot, angle);
+        }
+
+        // leaves are stored relative to their node, only the flattened copy has to move
+        self.get_leaves_flat();
+    }
+
+    fn update_pruned(&mut self) {
+        self.pruned.iter_mut().for_each(|p| *p = false);
+
+        for &edit in &self.edits {
+            if let Edit::Cut(node) = edit {
+                self.pruned[node] = true;
+            }
+        }
+
+        // children are always pushed after their parent
+        for ix in 1..self.nodes.len() {
+            self.pruned[ix] = self.pruned[ix] || self.pruned[self.parents[ix]];
+        }
     }
 
     pub fn get_tree(&self) -> Vec <(f64, f64)> {
+        let mut result: Vec <(f64, f64)> = self.root_segments(0.0, self.roots_progress).into_iter()
+            .flat_map(|(start, end)| self.sample(start, end))
+            .map(|p| (p.x, p.y))
+            .collect();
+
+        result.extend(self.get_branches(1..self.nodes.len()));
+
+        result
+    }
+
+    fn root_segments(&self, from: f64, to: f64) -> Vec <(Point, Point)> {
+        if to <= from {
+            return Vec::new();
+        }
+
+        self.roots.iter()
+            .flat_map(|root| utils::polyline_part(root, from, to))
+            .collect()
+    }
+
+    /// Branches leading to the node and everything growing from it.
+    pub fn get_subtree(&self, node: usize) -> Vec <(f64, f64)> {
+        self.get_branches(self.subtree(node).into_iter())
+    }
+
+    fn get_branches(&self, nodes: impl Iterator<Item = usize>) -> Vec <(f64, f64)> {
         let mut result: Vec <(f64, f64)> = Vec::new();
 
-        for (parent, n) in self.neighbours.iter().enumerate() {
-            for &child in n {
-                (0..1000).map(|dt| utils::linear_interpolate(&self.nodes[parent], &self.nodes[child], dt as f64 / 1000.0))
-                    .for_each(|p| result.push((p.x, p.y)));
-            }
+        for child in nodes.filter(|&child| child != 0 && !self.pruned[child]) {
+            let parent = self.parents[child];
+            let progress = self.edge_progress[child];
+
+            let end = utils::linear_interpolate(&self.nodes[parent], &self.nodes[child], progress);
+
+            self.sample(self.nodes[parent], end).for_each(|p| result.push((p.x, p.y)));
         }
 
         result
@@ -329,6 +1108,41 @@ impl BonsaiTree {
             .collect()
     }
 
+    pub fn get_blooms(&self) -> Vec <(f64, f64)> {
+        std::iter::zip(self.nodes.iter(), std::iter::zip(self.blooms.iter(), self.blooms_shown.iter()))
+            .zip(self.pruned.iter())
+            .filter(|(_, &pruned)| !pruned)
+            .flat_map(|((&node, (blooms, &shown)), _)| blooms[..shown].iter().map(move |&p| node + p))
+            .map(|p| (p.x, p.y))
+            .collect()
+    }
+
+    pub fn get_attached_leaves(&self) -> Vec <(f64, f64)> {
+        std::iter::zip(self.leaves_offset.iter(), self.leaves_shown.iter())
+            .zip(self.pruned.iter())
+            .filter(|(_, &pruned)| !pruned)
+            .flat_map(|((&offset, &shown), _)| self.leaves_flat[offset..offset + shown].iter())
+            .map(|p| (p.x, p.y))
+            .collect()
+    }
+
+    /// Attached leaves with how far out in their cluster they sit, from 0 in the middle of
+    /// a leaf circle to 1 on its outline.
+    pub fn get_shaded_leaves(&self) -> Vec <((f64, f64), f64)> {
+        (0..self.nodes.len())
+            .filter(|&ix| !self.pruned[ix])
+            .flat_map(|ix| self.leaves[ix][..self.leaves_shown[ix]].iter().map(move |&p| {
+                let unscaled = Point::from_floats(p.x / self.leaf_scale.0, p.y / self.leaf_scale.1);
+                let exposure = self.leaves_preprocess[ix].iter()
+                    .map(|&(center, radius)| f64::sqrt((unscaled - center).norm2()) / radius as f64)
+                    .fold(1.0, f64::min);
+
+                let leaf = self.nodes[ix] + p;
+                ((leaf.x, leaf.y), exposure)
+            }))
+            .collect()
+    }
+
     pub fn get_pot(&self) -> Vec <(f64, f64)> {
         let p1 = self.pot.iter().fold(Point::from_floats(self.bounds.0 as f64 / 2.0, 0.0), |a, &b| if a.x > b.x || a.y > b.y { a } else { b });
         let p2 = self.pot.iter().fold(Point::from_floats(self.bounds.0 as f64 / 2.0, 0.0), |a, &b| if a.x < b.x || a.y > b.y { a } else { b });
@@ -345,8 +1159,7 @@ impl BonsaiTree {
         ];
 
         let mut result: Vec <(f64, f64)> = std::iter::zip(self.pot.iter(), self.pot.iter().cycle().skip(1))
-            .map(|(p1, p2)| (0..1000).map(|dt| utils::linear_interpolate(p1, p2, dt as f64 / 1000.0)))
-            .flatten()
+            .flat_map(|(&p1, &p2)| self.sample(p1, p2))
             .map(|p| (p.x, p.y))
             .collect();
 
@@ -371,3 +1184,95 @@ fn get_pot_points(bounds: (u16, u16)) -> [Point; 4] {
     ]
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> BonsaiTree {
+        let mut tree = BonsaiTree::new(Rect::new(0, 0, 120, 40), Some(42), false, None, 0.0, GrowthStyle::default());
+        tree.generate();
+        tree.normalize();
+
+        tree
+    }
+
+    // a branch with more branches on it, so edits move a whole subtree
+    fn fork(tree: &BonsaiTree) -> usize {
+        (1..tree.nodes.len()).find(|&ix| !tree.neighbours[ix].is_empty()).unwrap()
+    }
+
+    fn assert_nodes_eq(a: &[Point], b: &[Point]) {
+        assert_eq!(a.len(), b.len());
+        for (ix, (a, b)) in a.iter().zip(b).enumerate() {
+            assert!(f64::abs(a.x - b.x) < 1e-9 && f64::abs(a.y - b.y) < 1e-9, "node {}: {:?} != {:?}", ix, a, b);
+        }
+    }
+
+    #[test]
+    fn undo_restores_rotated_and_bent_nodes() {
+        let mut tree = tree();
+        let node = fork(&tree);
+        let nodes = tree.nodes.clone();
+
+        for edit in [Edit::Rotate { node, angle: 0.3 }, Edit::Bend { node, angle: -0.2 }] {
+            tree.apply(edit);
+            assert!(f64::abs(tree.nodes[tree.neighbours[node][0]].x - nodes[tree.neighbours[node][0]].x) > 1e-3);
+
+            assert!(tree.undo());
+            assert_nodes_eq(&tree.nodes, &nodes);
+        }
+
+        assert!(!tree.undo());
+    }
+
+    #[test]
+    fn rotations_of_one_node_merge() {
+        let mut tree = tree();
+        let node = fork(&tree);
+
+        tree.apply(Edit::Rotate { node, angle: 0.1 });
+        tree.apply(Edit::Rotate { node, angle: 0.2 });
+        assert!(matches!(tree.edits(), [Edit::Rotate { node: n, angle }] if *n == node && f64::abs(angle - 0.3) < 1e-12));
+
+        tree.apply(Edit::Bend { node, angle: 0.1 });
+        tree.apply(Edit::Rotate { node, angle: 0.1 });
+        assert_eq!(tree.edits().len(), 3);
+    }
+
+    #[test]
+    fn undo_restores_cut_branches() {
+        let mut tree = tree();
+        let node = fork(&tree);
+        let pruned = tree.pruned.clone();
+
+        tree.apply(Edit::Cut(node));
+        assert!(tree.subtree(node).into_iter().all(|ix| tree.pruned[ix]));
+        assert_eq!(tree.pruned.iter().filter(|&&pruned| pruned).count(), tree.subtree(node).len());
+
+        tree.apply(Edit::Cut(tree.neighbours[node][0]));
+        assert!(tree.undo());
+        assert!(tree.undo());
+        assert_eq!(tree.pruned, pruned);
+    }
+
+    #[test]
+    fn replayed_edits_grow_the_same_tree() {
+        let mut edited = tree();
+        let node = fork(&edited);
+        let child = edited.neighbours[node][0];
+
+        edited.apply(Edit::Rotate { node, angle: 0.25 });
+        edited.apply(Edit::Bend { node: child, angle: -0.15 });
+        edited.apply(Edit::Cut(edited.nodes.len() - 1));
+        edited.apply(Edit::Rotate { node, angle: -0.05 });
+
+        let mut replayed = tree();
+        edited.edits().iter().for_each(|&edit| replayed.apply(edit));
+
+        assert_eq!(replayed.edits(), edited.edits());
+        assert_eq!(replayed.pruned, edited.pruned);
+        assert_nodes_eq(&replayed.nodes, &edited.nodes);
+        assert_eq!(replayed.get_leaves(), edited.get_leaves());
+    }
+}
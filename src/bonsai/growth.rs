@@ -0,0 +1,67 @@
+use clap::ValueEnum;
+
+/// How fast a branch extends over the steps it takes to grow.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Shoots out quickly and slows down towards the node.
+    EaseOut,
+    /// Overshoots the node and springs back to it.
+    Elastic,
+}
+
+impl Easing {
+    /// Maps the linear progress `t` in `[0, 1]` to the drawn length of the branch.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = f64::clamp(t, 0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOut => 1.0 - f64::powi(1.0 - t, 3),
+            Easing::Elastic if t == 0.0 || t == 1.0 => t,
+            Easing::Elastic => {
+                let period = 2.0 * std::f64::consts::PI / 3.0;
+
+                f64::powf(2.0, -10.0 * t) * f64::sin((10.0 * t - 0.75) * period) + 1.0
+            }
+        }
+    }
+
+    /// True if a branch can grow past its node and shrink again, so what was drawn can't just be extended.
+    pub fn overshoots(self) -> bool {
+        self == Easing::Elastic
+    }
+}
+
+/// Which branches grow at the same time.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrowthOrder {
+    /// A branch starts once the one it grows from is finished, leaves follow every branch.
+    #[default]
+    Tiered,
+    /// Every branch grows at once.
+    All,
+    /// All branches grow tier by tier first, the leaves only come once the last one is finished.
+    TrunkFirst,
+}
+
+/// Order in which the leaves of a cluster appear.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeafReveal {
+    /// From the branch outwards.
+    #[default]
+    Radial,
+    /// Randomly all over the cluster.
+    Sparkle,
+    /// From the top of the cluster down.
+    TopDown,
+}
+
+/// How the live animation grows the tree, doesn't change the tree itself.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GrowthStyle {
+    pub easing: Easing,
+    pub order: GrowthOrder,
+    pub reveal: LeafReveal,
+}
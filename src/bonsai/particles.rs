@@ -0,0 +1,157 @@
+use rand::Rng;
+
+use super::utils::Point;
+use super::PointType;
+
+const GRAVITY: f64 = 0.03;
+const DRAG: f64 = 0.08;
+const BROADSIDE_DRAG: f64 = 0.25;
+const WIND: f64 = 0.01;
+const FLUTTER: f64 = 0.05;
+
+const MIN_SPIN: f64 = 0.05;
+const MAX_SPIN: f64 = 0.35;
+
+const LANDED_LIFETIME: u32 = 300;
+const FADE_TIME: u32 = 80;
+const PILE_STEP: f64 = 0.25;
+
+pub const MAX_PARTICLES: usize = 150;
+
+#[derive(Debug, Clone, Copy)]
+enum ParticleState {
+    Falling,
+    Landed(u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Particle {
+    id: u64,
+    pos: Point,
+    vel: Point,
+    phase: f64,
+    spin: f64,
+    state: ParticleState,
+    debris: bool,
+}
+
+impl Particle {
+    pub fn new<R: Rng>(id: u64, pos: Point, rng: &mut R) -> Self {
+        Particle {
+            id,
+            pos,
+            vel: Point::from_floats(0.0, 0.0),
+            phase: rng.gen_range(0.0..std::f64::consts::TAU),
+            spin: rng.gen_range(MIN_SPIN..MAX_SPIN),
+            state: ParticleState::Falling,
+            debris: false,
+        }
+    }
+
+    /// A piece of a pruned branch, drawn like the trunk instead of a leaf.
+    pub fn debris<R: Rng>(id: u64, pos: Point, rng: &mut R) -> Self {
+        Particle {
+            debris: true,
+            ..Particle::new(id, pos, rng)
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// How the particle is drawn in its current state.
+    pub fn point(&self) -> PointType {
+        match self.state {
+            _ if self.debris => PointType::Debris(self.pos),
+            ParticleState::Landed(age) if age + FADE_TIME >= LANDED_LIFETIME => PointType::Fading(self.pos),
+            _ => PointType::Particle(self.pos),
+        }
+    }
+
+    /// Advances the particle by one tick. Returns the point to draw, or `None`
+    /// once the particle left the canvas or faded out.
+    pub fn step(&mut self, wind: Point, ground: &mut Ground) -> Option<PointType> {
+        match self.state {
+            ParticleState::Falling => {
+                self.phase += self.spin;
+
+                // a leaf turned broadside to the fall catches more air
+                let broadside = f64::abs(f64::cos(self.phase));
+                let drag = DRAG + BROADSIDE_DRAG * broadside;
+
+                let flutter = FLUTTER * f64::sin(self.phase);
+                let acc = Point::from_floats(wind.x * WIND + flutter, -GRAVITY);
+
+                self.vel = self.vel + acc - self.vel * drag;
+                self.pos = self.pos + self.vel;
+
+                if !ground.contains(self.pos.x) {
+                    return None;
+                }
+
+                if self.pos.y <= ground.surface(self.pos.x) {
+                    self.pos.y = ground.land(self.pos.x);
+                    self.state = ParticleState::Landed(0);
+                }
+
+                Some(self.point())
+            }
+
+            ParticleState::Landed(age) if age >= LANDED_LIFETIME => {
+                ground.release(self.pos.x);
+                None
+            }
+
+            ParticleState::Landed(age) => {
+                self.state = ParticleState::Landed(age + 1);
+
+                Some(self.point())
+            }
+        }
+    }
+}
+
+/// Surface the particles collide with: the upper outline of the pot polygon,
+/// the ground line elsewhere, plus whatever already piled up in each column.
+#[derive(Clone)]
+pub struct Ground {
+    pot: [Point; 4],
+    pile: Vec <f64>,
+}
+
+impl Ground {
+    pub fn new(pot: [Point; 4], width: u16) -> Self {
+        Ground {
+            pot,
+            pile: vec![0.0; width as usize],
+        }
+    }
+
+    fn contains(&self, x: f64) -> bool {
+        x >= 0.0 && (x as usize) < self.pile.len()
+    }
+
+    fn base(&self, x: f64) -> f64 {
+        std::iter::zip(self.pot.iter(), self.pot.iter().cycle().skip(1))
+            .filter(|(a, b)| f64::min(a.x, b.x) <= x && x <= f64::max(a.x, b.x) && a.x != b.x)
+            .map(|(a, b)| a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x))
+            .fold(0.0, f64::max)
+    }
+
+    fn surface(&self, x: f64) -> f64 {
+        self.base(x) + self.pile[x as usize]
+    }
+
+    fn land(&mut self, x: f64) -> f64 {
+        let y = self.surface(x);
+        self.pile[x as usize] += PILE_STEP;
+
+        y
+    }
+
+    fn release(&mut self, x: f64) {
+        let column = &mut self.pile[x as usize];
+        *column = f64::max(0.0, *column - PILE_STEP);
+    }
+}
@@ -0,0 +1,98 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Summer,
+    Autumn,
+    Winter,
+    Spring,
+}
+
+/// Counts animation ticks once the tree finished growing and splits them
+/// into four equally long seasons.
+#[derive(Clone)]
+pub struct SeasonClock {
+    year: u32,
+    tick: u32,
+}
+
+impl SeasonClock {
+    pub fn new(year: u32) -> Self {
+        SeasonClock {
+            year: u32::max(year, 4),
+            tick: 0,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.tick = (self.tick + 1) % self.year;
+    }
+
+    pub fn season(&self) -> Season {
+        match 4 * self.tick as u64 / self.year as u64 {
+            0 => Season::Summer,
+            1 => Season::Autumn,
+            2 => Season::Winter,
+            _ => Season::Spring,
+        }
+    }
+
+    /// Fraction of the current season that already passed, in `[0, 1)`.
+    pub fn progress(&self) -> f64 {
+        let length = self.year as f64 / 4.0;
+
+        (self.tick as f64 % length) / length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn after(year: u32, ticks: u32) -> SeasonClock {
+        let mut clock = SeasonClock::new(year);
+        (0..ticks).for_each(|_| clock.tick());
+
+        clock
+    }
+
+    #[test]
+    fn seasons_change_at_quarters() {
+        let seasons = [(0, Season::Summer), (24, Season::Summer), (25, Season::Autumn), (49, Season::Autumn),
+            (50, Season::Winter), (74, Season::Winter), (75, Season::Spring), (99, Season::Spring)];
+
+        for (ticks, season) in seasons {
+            assert_eq!(after(100, ticks).season(), season, "after {} ticks", ticks);
+        }
+    }
+
+    #[test]
+    fn progress_restarts_every_season() {
+        assert_eq!(after(100, 0).progress(), 0.0);
+        assert_eq!(after(100, 10).progress(), 0.4);
+        assert_eq!(after(100, 25).progress(), 0.0);
+        assert_eq!(after(100, 99).progress(), 0.96);
+    }
+
+    #[test]
+    fn year_wraps_around() {
+        let clock = after(100, 100);
+
+        assert_eq!(clock.season(), Season::Summer);
+        assert_eq!(clock.progress(), 0.0);
+        assert_eq!(after(100, 130).season(), Season::Autumn);
+    }
+
+    #[test]
+    fn years_are_at_least_four_ticks() {
+        let seasons: Vec <Season> = (0..5).map(|ticks| after(0, ticks).season()).collect();
+
+        assert_eq!(seasons, [Season::Summer, Season::Autumn, Season::Winter, Season::Spring, Season::Summer]);
+    }
+
+    #[test]
+    fn long_years_dont_overflow() {
+        let mut clock = SeasonClock::new(u32::MAX);
+        clock.tick = u32::MAX - 1;
+
+        assert_eq!(clock.season(), Season::Spring);
+    }
+}
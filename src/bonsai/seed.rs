@@ -0,0 +1,35 @@
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Generator every tree is grown with. ChaCha8 from `rand_chacha` produces the same
+/// stream for the same key on every platform and in every release of the crate, unlike
+/// `StdRng`, whose algorithm may change with any version of `rand`.
+pub type TreeRng = ChaCha8Rng;
+
+/// Generator for `seed`, its 32 byte key is filled by SplitMix64 started at the seed,
+/// so the mapping doesn't depend on how `rand_core` happens to expand a `u64`.
+pub fn rng(seed: u64) -> TreeRng {
+    let mut state = seed;
+    let mut key = [0u8; 32];
+
+    for chunk in key.chunks_exact_mut(8) {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        chunk.copy_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+    }
+
+    TreeRng::from_seed(key)
+}
+
+/// Reads a seed as typed by the user, surrounding whitespace aside: a number is the seed itself, any
+/// other text is hashed with 64 bit FNV-1a over its UTF-8 bytes, so "team-offsite" is the same tree everywhere.
+pub fn parse_seed(text: &str) -> u64 {
+    let text = text.trim();
+
+    text.parse().unwrap_or_else(|_| {
+        text.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3))
+    })
+}
@@ -18,6 +18,13 @@ impl Point {
         }
     }
 
+    pub fn rotate(&self, pivot: &Point, angle: f64) -> Point {
+        let (sin, cos) = f64::sin_cos(angle);
+        let offset = *self - *pivot;
+
+        *pivot + Point::from_floats(offset.x * cos - offset.y * sin, offset.x * sin + offset.y * cos)
+    }
+
     pub fn norm2(&self) -> f64 {
         self.x * self.x + self.y * self.y
     }
@@ -41,6 +48,44 @@ pub fn linear_interpolate(start: &Point, end: &Point, dt: f64) -> Point {
     *start + (*end - *start) * dt
 }
 
+/// Pieces of the line through `points` from `from` to `to` of its length, both between 0 and 1.
+pub fn polyline_part(points: &[Point], from: f64, to: f64) -> Vec <(Point, Point)> {
+    let lengths: Vec <f64> = points.windows(2).map(|pair| f64::sqrt((pair[1] - pair[0]).norm2())).collect();
+    let total: f64 = lengths.iter().sum();
+    let (from, to) = (from * total, to * total);
+
+    let mut result = Vec::new();
+    let mut start = 0.0;
+
+    for (pair, &length) in points.windows(2).zip(lengths.iter()) {
+        let end = start + length;
+
+        if length > 0.0 && end > from && start < to {
+            result.push((
+                linear_interpolate(&pair[0], &pair[1], (f64::max(from, start) - start) / length),
+                linear_interpolate(&pair[0], &pair[1], (f64::min(to, end) - start) / length),
+            ));
+        }
+
+        start = end;
+    }
+
+    result
+}
+
+pub fn segment_distance(p: &Point, start: &Point, end: &Point) -> f64 {
+    let segment = *end - *start;
+    let offset = *p - *start;
+
+    let t = if segment.norm2() > 0.0 {
+        f64::clamp((offset.x * segment.x + offset.y * segment.y) / segment.norm2(), 0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    f64::sqrt((offset - segment * t).norm2())
+}
+
 
 impl Add for Point {
     type Output = Self;
@@ -0,0 +1,64 @@
+use std::fmt;
+use std::io;
+
+/// Everything that can stop the bonsai from growing.
+#[derive(Debug)]
+pub enum BonsaiError {
+    Io(io::Error),
+    /// Every tree needs at least `min` cells, `(width, height)`, and there are `count` of them.
+    TerminalTooSmall { size: (u16, u16), min: (u16, u16), count: u16 },
+}
+
+impl fmt::Display for BonsaiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BonsaiError::Io(e) => write!(f, "{}", e),
+            BonsaiError::TerminalTooSmall { size, min, count: 1 } => write!(
+                f,
+                "the terminal is {}x{}, the tree needs at least {}x{}",
+                size.0, size.1, min.0, min.1,
+            ),
+            BonsaiError::TerminalTooSmall { size, min, count } => write!(
+                f,
+                "the terminal is {}x{}, {} trees need at least {}x{}, try a smaller --count",
+                size.0, size.1, count, min.0 as u32 * *count as u32, min.1,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BonsaiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BonsaiError::Io(e) => Some(e),
+            BonsaiError::TerminalTooSmall { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for BonsaiError {
+    fn from(e: io::Error) -> Self {
+        BonsaiError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_small_for_one_tree() {
+        let error = BonsaiError::TerminalTooSmall { size: (10, 5), min: (16, 8), count: 1 };
+
+        assert_eq!(error.to_string(), "the terminal is 10x5, the tree needs at least 16x8");
+    }
+
+    #[test]
+    fn too_small_for_a_garden() {
+        let error = BonsaiError::TerminalTooSmall { size: (80, 24), min: (16, 8), count: 10 };
+        assert_eq!(error.to_string(), "the terminal is 80x24, 10 trees need at least 160x8, try a smaller --count");
+
+        let error = BonsaiError::TerminalTooSmall { size: (80, 24), min: (16, 8), count: u16::MAX };
+        assert_eq!(error.to_string(), "the terminal is 80x24, 65535 trees need at least 1048560x8, try a smaller --count");
+    }
+}
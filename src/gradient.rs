@@ -0,0 +1,115 @@
+use ratatui::style::Color;
+
+const SHADES: usize = 6;
+
+// hue rotations in radians picked per point, so neighbouring points differ slightly
+const HUE_JITTER: [f64; 3] = [-0.12, 0.0, 0.12];
+
+/// Shades of one color to draw a layer with instead of a single flat color.
+/// Every point gets a shade from 0, darkest, to 1, lightest, and a hue jitter that
+/// depends only on its position, so redrawing the layer doesn't make it flicker.
+/// There are only a few shades, each of them one color on the canvas.
+pub struct Gradient {
+    darken: f64,
+    lighten: f64,
+
+    shades: usize,
+    jitters: usize,
+
+    colors: Vec <Color>,
+}
+
+impl Gradient {
+    /// Shades go from `base` mixed with `darken` of black up to `base` mixed with `lighten` of white.
+    pub fn new(base: Color, darken: f64, lighten: f64) -> Self {
+        Self::with_buckets(base, darken, lighten, SHADES, HUE_JITTER.len())
+    }
+
+    /// Every point in the one `base` color.
+    pub fn flat(base: Color) -> Self {
+        Self::with_buckets(base, 0.0, 0.0, 1, 1)
+    }
+
+    fn with_buckets(base: Color, darken: f64, lighten: f64, shades: usize, jitters: usize) -> Self {
+        let mut gradient = Gradient {
+            darken,
+            lighten,
+
+            shades,
+            jitters,
+
+            colors: Vec::new(),
+        };
+
+        gradient.set_color(base);
+
+        gradient
+    }
+
+    /// Changes the color the shades are made from.
+    pub fn set_color(&mut self, base: Color) {
+        let dark = lerp_color(base, Color::Rgb(0, 0, 0), self.darken);
+        let light = lerp_color(base, Color::Rgb(255, 255, 255), self.lighten);
+
+        self.colors = (0..self.shades)
+            .flat_map(|shade| {
+                let t = if self.shades > 1 { shade as f64 / (self.shades - 1) as f64 } else { 0.5 };
+                let color = if t < 0.5 { lerp_color(dark, base, 2.0 * t) } else { lerp_color(base, light, 2.0 * t - 1.0) };
+
+                let angles: &[f64] = if self.jitters > 1 { &HUE_JITTER } else { &[0.0] };
+                angles.iter().map(move |&angle| rotate_hue(color, angle))
+            })
+            .collect();
+    }
+
+    pub fn color(&self, (x, y): (f64, f64), shade: f64) -> Color {
+        let shade = f64::round(f64::clamp(shade, 0.0, 1.0) * (self.shades - 1) as f64) as usize;
+        let jitter = (hash(x, y) % self.jitters as u64) as usize;
+
+        self.colors[shade * self.jitters + jitter]
+    }
+}
+
+pub(crate) fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Green => Some((0, 128, 0)),
+        Color::LightGreen => Some((144, 238, 144)),
+        _ => None,
+    }
+}
+
+pub(crate) fn lerp_color(from: Color, to: Color, t: f64) -> Color {
+    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+
+    match (rgb(from), rgb(to)) {
+        (Some(a), Some(b)) => Color::Rgb(lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2)),
+        _ if t < 0.5 => from,
+        _ => to,
+    }
+}
+
+// rotation around the gray axis of the rgb cube, keeps the brightness roughly the same
+fn rotate_hue(color: Color, angle: f64) -> Color {
+    let Some((r, g, b)) = rgb(color) else { return color };
+    if angle == 0.0 {
+        return color;
+    }
+
+    let (sin, cos) = f64::sin_cos(angle);
+    let (r, g, b) = (r as f64, g as f64, b as f64);
+    let channel = |v: f64| f64::clamp(v, 0.0, 255.0).round() as u8;
+
+    Color::Rgb(
+        channel((0.299 + 0.701 * cos + 0.168 * sin) * r + (0.587 - 0.587 * cos + 0.330 * sin) * g + (0.114 - 0.114 * cos - 0.497 * sin) * b),
+        channel((0.299 - 0.299 * cos - 0.328 * sin) * r + (0.587 + 0.413 * cos + 0.035 * sin) * g + (0.114 - 0.114 * cos + 0.292 * sin) * b),
+        channel((0.299 - 0.300 * cos + 1.250 * sin) * r + (0.587 - 0.588 * cos - 1.050 * sin) * g + (0.114 + 0.886 * cos - 0.203 * sin) * b),
+    )
+}
+
+fn hash(x: f64, y: f64) -> u64 {
+    let mut h = x.to_bits().wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ y.to_bits();
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^ (h >> 33)
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use ratatui::style::Color;
+use ratatui::widgets::canvas::{Context, Points};
+
+/// Layers of the tree kept in a [`DotGrid`], later ones cover earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Trunk,
+    Leaves,
+}
+
+const LAYERS: usize = 2;
+
+/// Points snapped to the dots the canvas can show, so a dot is stored once however many
+/// points land on it. Every dot remembers the color each layer painted it with and shows
+/// the top one, so shedding leaves uncovers the trunk under them again. Dots that changed
+/// are collected and only they are moved between the color groups handed to the canvas.
+pub struct DotGrid {
+    // dots horizontally and vertically, as the canvas counts them
+    size: (usize, usize),
+    bounds: (f64, f64),
+
+    dots: Vec <[Option<Color>; LAYERS]>,
+    // group and position in it every shown dot is drawn from
+    shown: Vec <Option<(usize, usize)>>,
+    changed: Vec <usize>,
+
+    groups: HashMap <Color, usize>,
+    points: Vec <(Color, Vec <(f64, f64)>)>,
+    // dot of every point in `points`, to find it again when another one takes its place
+    members: Vec <Vec <usize>>,
+}
+
+impl DotGrid {
+    pub fn new(size: (usize, usize), bounds: (f64, f64)) -> Self {
+        DotGrid {
+            size,
+            bounds,
+
+            dots: vec![[None; LAYERS]; size.0 * size.1],
+            shown: vec![None; size.0 * size.1],
+            changed: Vec::new(),
+
+            groups: HashMap::new(),
+            points: Vec::new(),
+            members: Vec::new(),
+        }
+    }
+
+    // the same mapping the canvas uses to find the dot of a point
+    fn index(&self, (x, y): (f64, f64)) -> Option<usize> {
+        let (width, height) = self.size;
+        if width < 2 || height < 2 || !(0.0..=self.bounds.0).contains(&x) || !(0.0..=self.bounds.1).contains(&y) {
+            return None;
+        }
+
+        let column = (x * (width - 1) as f64 / self.bounds.0) as usize;
+        let row = ((self.bounds.1 - y) * (height - 1) as f64 / self.bounds.1) as usize;
+
+        Some(row * width + column)
+    }
+
+    // a point in the middle of the dot, clamped so the last row and column stay inside the canvas
+    fn point(&self, index: usize) -> (f64, f64) {
+        let (width, height) = self.size;
+        let (column, row) = (index % width, index / width);
+
+        (
+            f64::min(self.bounds.0, (column as f64 + 0.5) * self.bounds.0 / (width - 1) as f64),
+            f64::max(0.0, self.bounds.1 - (row as f64 + 0.5) * self.bounds.1 / (height - 1) as f64),
+        )
+    }
+
+    pub fn insert(&mut self, layer: Layer, p: (f64, f64), color: Color) {
+        if let Some(index) = self.index(p) {
+            if self.dots[index][layer as usize] != Some(color) {
+                self.dots[index][layer as usize] = Some(color);
+                self.changed.push(index);
+            }
+        }
+    }
+
+    pub fn clear(&mut self, layer: Layer) {
+        for (index, dot) in self.dots.iter_mut().enumerate() {
+            if dot[layer as usize].take().is_some() {
+                self.changed.push(index);
+            }
+        }
+    }
+
+    /// Moves the dots changed since the last call to the group of their new top color.
+    pub fn update(&mut self) {
+        let changed = std::mem::take(&mut self.changed);
+
+        for index in changed {
+            let color = self.dots[index].iter().rev().find_map(|&color| color);
+
+            match self.shown[index] {
+                Some((group, _)) if Some(self.points[group].0) == color => continue,
+                Some(_) => self.hide(index),
+                None => (),
+            }
+
+            if let Some(color) = color {
+                self.show(index, color);
+            }
+        }
+    }
+
+    fn show(&mut self, index: usize, color: Color) {
+        let group = *self.groups.entry(color).or_insert_with(|| {
+            self.points.push((color, Vec::new()));
+            self.members.push(Vec::new());
+            self.points.len() - 1
+        });
+
+        let point = self.point(index);
+        self.shown[index] = Some((group, self.points[group].1.len()));
+        self.points[group].1.push(point);
+        self.members[group].push(index);
+    }
+
+    fn hide(&mut self, index: usize) {
+        let Some((group, position)) = self.shown[index].take() else { return };
+
+        self.points[group].1.swap_remove(position);
+        self.members[group].swap_remove(position);
+
+        // the last dot of the group moved into the gap
+        if let Some(&moved) = self.members[group].get(position) {
+            self.shown[moved] = Some((group, position));
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) {
+        for (color, coords) in &self.points {
+            ctx.draw(&Points { coords, color: *color });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drawn(grid: &DotGrid) -> Vec <(Color, Vec <(i64, i64)>)> {
+        let mut drawn: Vec <_> = grid.points.iter()
+            .filter(|(_, coords)| !coords.is_empty())
+            .map(|(color, coords)| {
+                let mut coords: Vec <_> = coords.iter().map(|&(x, y)| ((x * 100.0) as i64, (y * 100.0) as i64)).collect();
+                coords.sort();
+                (*color, coords)
+            })
+            .collect();
+        drawn.sort_by_key(|(color, _)| format!("{:?}", color));
+
+        drawn
+    }
+
+    #[test]
+    fn changes_match_a_fresh_grid() {
+        let mut grid = DotGrid::new((40, 20), (20.0, 10.0));
+        for x in 0..20 {
+            grid.insert(Layer::Trunk, (x as f64, 2.0), Color::Red);
+            grid.insert(Layer::Leaves, (x as f64, 2.0 + (x % 3) as f64), Color::Green);
+        }
+        grid.update();
+
+        grid.clear(Layer::Leaves);
+        for x in (0..20).step_by(2) {
+            grid.insert(Layer::Leaves, (x as f64, 2.0), Color::Yellow);
+        }
+        grid.insert(Layer::Trunk, (5.0, 8.0), Color::Red);
+        grid.update();
+
+        let mut fresh = DotGrid::new((40, 20), (20.0, 10.0));
+        for x in 0..20 {
+            fresh.insert(Layer::Trunk, (x as f64, 2.0), Color::Red);
+        }
+        for x in (0..20).step_by(2) {
+            fresh.insert(Layer::Leaves, (x as f64, 2.0), Color::Yellow);
+        }
+        fresh.insert(Layer::Trunk, (5.0, 8.0), Color::Red);
+        fresh.update();
+
+        assert_eq!(drawn(&grid), drawn(&fresh));
+        assert!(grid.changed.is_empty());
+    }
+}
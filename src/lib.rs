@@ -0,0 +1,12 @@
+pub mod animator;
+pub mod app;
+pub mod bonsai;
+pub mod error;
+pub mod gradient;
+pub mod grid;
+pub mod palette;
+pub mod plot;
+pub mod saved;
+pub mod search;
+pub mod stats;
+pub mod weather;
@@ -1,25 +1,241 @@
-pub mod bonsai;
-use clap::Parser;
-use std::io;
-pub mod app;
-use app::App;
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use std::convert::Infallible;
+use std::process;
+use std::path::PathBuf;
+use rustbonsai::app::{App, Bloom, CanvasMarker, Settings};
+use rustbonsai::bonsai::growth::{Easing, GrowthOrder, LeafReveal};
+use rustbonsai::bonsai::seed::parse_seed;
+use rustbonsai::palette::ColorMode;
+use rustbonsai::search::{self, Criteria, Lean};
+use rustbonsai::stats::TreeStats;
+use rustbonsai::weather::WeatherKind;
+
+// cells of every tree drawn by `search --gallery`, wide enough for the seed in its title
+const THUMBNAIL: (u16, u16) = (32, 14);
+
+// longest live growth `--duration` takes, a day
+const MAX_DURATION: f64 = 86_400.0;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    #[arg(short, long, help = "Specify u64 number to generate seed for simulation if not specified will be random")]
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(short, long, value_parser = seed, help = "Specify u64 number or any text to generate seed for simulation if not specified will be random")]
     seed: Option<u64>,
     #[arg(short, long, default_value_t = false, help = "If included will show live simulation")]
     live: bool,
     #[arg(short, long, default_value_t = false, help = "If included will show particles")]
     particles: bool, 
-    #[arg(short, long, default_value_t = 1, help = "Change color scheme: 1 Basic, 2 Cherry, 3 Maple, 4 Avatar")]
+    #[arg(short, long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..=4), help = "Change color scheme: 1 Basic, 2 Cherry, 3 Maple, 4 Avatar")]
     theme: u16,
+    #[arg(short, long, value_enum, default_value_t = WeatherKind::Clear, help = "Weather drawn over the tree, press 'w' to cycle and '+'/'-' to change intensity")]
+    weather: WeatherKind,
+    #[arg(long, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "120", help = "Cycle through the seasons once the tree has grown, one year lasting SECONDS [default: 120]")]
+    seasons: Option<u32>,
+    #[arg(short, long, value_enum, default_value_t = Bloom::None, help = "Grow flowers or fruit on the edges of leaf clusters")]
+    bloom: Bloom,
+    #[arg(long, default_value_t = 0.05, help = "Fraction of leaf cluster edge points that bloom")]
+    bloom_density: f64,
+    #[arg(long, default_value_t = false, help = "Plant a persistent tree that grows with real time over two weeks, kept in $XDG_DATA_HOME/rustbonsai")]
+    grow_realtime: bool,
+    #[arg(long, value_name = "FILE", help = "Keep the tree and its pruning and wiring in FILE, loading it if it exists; 's' saves, 'u' undoes the last edit")]
+    save: Option<PathBuf>,
+    #[arg(short, long, default_value_t = 1, conflicts_with_all = ["save", "grow_realtime"], help = "Grow a garden of COUNT trees side by side, each with its own seed and theme")]
+    count: u16,
+    #[arg(short, long, value_name = "SECONDS", value_parser = duration, help = "Make the live growth take SECONDS of wall time, whatever the tree size or frame rate")]
+    duration: Option<f64>,
+    #[arg(long, value_enum, default_value_t = Easing::Linear, help = "How fast every branch extends while it grows")]
+    easing: Easing,
+    #[arg(long, value_enum, default_value_t = GrowthOrder::Tiered, help = "Which branches grow at the same time")]
+    growth_order: GrowthOrder,
+    #[arg(long, value_enum, default_value_t = LeafReveal::Radial, help = "Order in which the leaves of a cluster appear")]
+    leaf_reveal: LeafReveal,
+    #[arg(long, default_value_t = false, help = "Draw the trunk and leaves in one flat color each instead of gradients")]
+    no_gradient: bool,
+    #[arg(long, value_enum, default_value_t = ColorMode::Auto, help = "Colors the terminal can show, detected from NO_COLOR, COLORTERM and TERM by default")]
+    color: ColorMode,
+    #[arg(short, long, value_enum, default_value_t = CanvasMarker::Dot, help = "Symbol the tree is drawn with, press 'm' to cycle")]
+    marker: CanvasMarker,
+    #[arg(long, default_value_t = false, help = "Grow surface roots from the base of the trunk over the rim of the pot, before the trunk in live mode")]
+    roots: bool,
+    #[arg(long, default_value_t = false, conflicts_with_all = ["live", "save", "grow_realtime", "count"], help = "Flip through seeds with the left and right arrows, Enter marks the shown seed as a favorite")]
+    browse: bool,
+    #[arg(long, value_name = "FILE", requires = "browse", help = "Keep the favorite seeds in FILE, one per line [default: $XDG_DATA_HOME/rustbonsai/favorites.txt]")]
+    favorites: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Grow a tree without drawing it and print what it is made of
+    Inspect(Inspect),
+    /// Grow many trees without drawing them and print the seeds of those matching the criteria
+    Search(Search),
+}
+
+#[derive(ClapArgs, Debug)]
+struct Inspect {
+    #[arg(short, long, value_parser = seed, help = "Seed of the tree, a u64 number or any text, random if not specified")]
+    seed: Option<u64>,
+    #[arg(long, default_value_t = 120, help = "Width of the plot the tree grows in")]
+    width: u16,
+    #[arg(long, default_value_t = 40, help = "Height of the plot the tree grows in")]
+    height: u16,
+    #[arg(long, default_value_t = false, help = "Print the statistics as JSON")]
+    json: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+struct Search {
+    #[arg(long, help = "Least leaves a tree has to have")]
+    min_leaves: Option<usize>,
+    #[arg(long, help = "Most leaves a tree can have")]
+    max_leaves: Option<usize>,
+    #[arg(long, help = "Least branches, the trunk included, a tree has to have")]
+    min_branches: Option<usize>,
+    #[arg(long, help = "Most branches, the trunk included, a tree can have")]
+    max_branches: Option<usize>,
+    #[arg(long, help = "Least share of the plot's width the tree has to span, from 0 to 1")]
+    min_width: Option<f64>,
+    #[arg(long, help = "Most share of the plot's width the tree can span, from 0 to 1")]
+    max_width: Option<f64>,
+    #[arg(long, help = "Least width of the tree divided by its height")]
+    min_ratio: Option<f64>,
+    #[arg(long, help = "Most width of the tree divided by its height")]
+    max_ratio: Option<f64>,
+    #[arg(long, help = "Least symmetry of the tree around its middle, from 0 to 1")]
+    min_symmetry: Option<f64>,
+    #[arg(long, value_enum, help = "Prefer trees leaning this way, otherwise the most symmetric ones come first")]
+    lean: Option<Lean>,
+    #[arg(short, long, default_value_t = 10, help = "How many of the best seeds to print")]
+    count: usize,
+    #[arg(long, default_value_t = 1000, help = "How many seeds to try")]
+    tries: u64,
+    #[arg(long, value_name = "SEED", help = "First seed to try, the following ones are tried in order, random if not specified")]
+    from: Option<u64>,
+    #[arg(long, default_value_t = 120, help = "Width of the plot the trees grow in")]
+    width: u16,
+    #[arg(long, default_value_t = 40, help = "Height of the plot the trees grow in")]
+    height: u16,
+    #[arg(short, long, default_value_t = false, help = "Also draw the trees found, side by side")]
+    gallery: bool,
+    #[arg(long, default_value_t = false, help = "Print the statistics of the trees found as JSON")]
+    json: bool,
+}
+
+fn seed(text: &str) -> Result<u64, Infallible> {
+    Ok(parse_seed(text))
+}
+
+fn duration(text: &str) -> Result<f64, String> {
+    let secs: f64 = text.parse().map_err(|e| format!("{}", e))?;
+
+    if (0.0..=MAX_DURATION).contains(&secs) {
+        Ok(secs)
+    } else {
+        Err(format!("{} is not in 0..={}", text, MAX_DURATION))
+    }
+}
+
+fn inspect(args: Inspect) {
+    let stats = TreeStats::new(args.seed.unwrap_or_else(rand::random), (args.width, args.height));
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&stats).expect("statistics are plain data"));
+    } else {
+        println!("{}", stats);
+    }
 }
 
-fn main() ->io::Result<()> {
+fn search(args: Search) -> std::io::Result<()> {
+    let criteria = Criteria {
+        min_leaves: args.min_leaves,
+        max_leaves: args.max_leaves,
+        min_branches: args.min_branches,
+        max_branches: args.max_branches,
+        min_width: args.min_width,
+        max_width: args.max_width,
+        min_ratio: args.min_ratio,
+        max_ratio: args.max_ratio,
+        min_symmetry: args.min_symmetry,
+
+        lean: args.lean,
+    };
+
+    let from = args.from.unwrap_or_else(rand::random);
+    let found = search::search(&criteria, from..from.saturating_add(args.tries), (args.width, args.height), args.count);
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&found).expect("statistics are plain data"));
+    } else {
+        println!("{:<20} {:>6} {:>8} {:>5} {:>5} {:>5} {:>8}", "seed", "leaves", "branches", "width", "ratio", "lean", "symmetry");
+        for stats in &found {
+            println!(
+                "{:<20} {:>6} {:>8} {:>5.2} {:>5.2} {:>5.2} {:>8.2}",
+                stats.seed, stats.leaves, stats.branches(), stats.width(), stats.aspect_ratio(), stats.lean, stats.symmetry,
+            );
+        }
+
+        if found.is_empty() {
+            println!("none of the {} seeds from {} matched, try more --tries or looser limits", args.tries, from);
+        }
+    }
+
+    if args.gallery && !found.is_empty() {
+        let columns = crossterm::terminal::size().map_or(120, |(width, _)| width) / THUMBNAIL.0;
+        let seeds: Vec <u64> = found.iter().map(|stats| stats.seed).collect();
+
+        println!();
+        search::gallery(&seeds, THUMBNAIL, columns)?.iter().for_each(|line| println!("{}", line));
+    }
+
+    Ok(())
+}
+
+fn main() {
     let args = Args::parse();
-    App::run(args.seed,args.live, args.theme, args.particles)
+
+    match args.command {
+        Some(Command::Inspect(inspect_args)) => return inspect(inspect_args),
+        Some(Command::Search(search_args)) => {
+            if let Err(e) = search(search_args) {
+                eprintln!("rustbonsai: {}", e);
+                process::exit(1);
+            }
+
+            return;
+        }
+        None => {}
+    }
+
+    let result = App::run(Settings {
+        seed: args.seed,
+        live: args.live,
+        theme: args.theme,
+        particles: args.particles,
+        weather: args.weather,
+        seasons: args.seasons,
+        bloom: args.bloom,
+        bloom_density: args.bloom_density,
+        grow_realtime: args.grow_realtime,
+        save: args.save,
+        count: args.count,
+        duration: args.duration,
+        easing: args.easing,
+        growth_order: args.growth_order,
+        leaf_reveal: args.leaf_reveal,
+        gradient: !args.no_gradient,
+        color: args.color,
+        marker: args.marker,
+        roots: args.roots,
+        browse: args.browse,
+        favorites: args.favorites,
+    });
+
+    if let Err(e) = result {
+        eprintln!("rustbonsai: {}", e);
+        process::exit(1);
+    }
 }
 
 
@@ -0,0 +1,165 @@
+use clap::ValueEnum;
+use ratatui::buffer::Buffer;
+use ratatui::style::Color;
+
+// xterm's default values of the 16 ansi colors, black is left out since it disappears on dark terminals
+const ANSI: [(Color, (u8, u8, u8)); 15] = [
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Colors the terminal can show, everything drawn is mapped to the closest of them.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Detected from `NO_COLOR`, `COLORTERM` and `TERM`.
+    Auto,
+    Truecolor,
+    #[value(name = "256")]
+    Ansi256,
+    #[value(name = "16")]
+    Ansi16,
+    /// Everything in the terminal's default color.
+    None,
+}
+
+impl ColorMode {
+    /// Replaces `Auto` with what the environment says the terminal supports.
+    pub fn resolve(self) -> Self {
+        match self {
+            ColorMode::Auto => ColorMode::detect(
+                std::env::var("NO_COLOR").ok().as_deref(),
+                std::env::var("COLORTERM").ok().as_deref(),
+                std::env::var("TERM").ok().as_deref(),
+            ),
+            mode => mode,
+        }
+    }
+
+    /// Follows https://no-color.org, then the usual `COLORTERM` and `TERM` conventions.
+    pub fn detect(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            return ColorMode::None;
+        }
+
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            return ColorMode::Truecolor;
+        }
+
+        match term {
+            None | Some("" | "dumb") => ColorMode::None,
+            Some(term) if term.ends_with("-direct") => ColorMode::Truecolor,
+            Some(term) if term.contains("256color") => ColorMode::Ansi256,
+            Some(_) => ColorMode::Ansi16,
+        }
+    }
+
+    /// True if there are enough colors for shading to be more than noise.
+    pub fn has_gradients(self) -> bool {
+        matches!(self, ColorMode::Auto | ColorMode::Truecolor | ColorMode::Ansi256)
+    }
+
+    pub fn map(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorMode::None, _) => Color::Reset,
+            (ColorMode::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256((r, g, b))),
+            (ColorMode::Ansi16, Color::Rgb(r, g, b)) => nearest_16((r, g, b)),
+            _ => color,
+        }
+    }
+
+    /// Maps the colors of every cell drawn in the frame.
+    pub fn apply(self, buffer: &mut Buffer) {
+        if matches!(self, ColorMode::Auto | ColorMode::Truecolor) {
+            return;
+        }
+
+        for cell in buffer.content.iter_mut() {
+            cell.fg = self.map(cell.fg);
+            cell.bg = self.map(cell.bg);
+        }
+    }
+}
+
+// weighted for how the eye sees differences, see https://www.compuphase.com/cmetric.htm
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> f64 {
+    let mean_r = (a.0 as f64 + b.0 as f64) / 2.0;
+    let (dr, dg, db) = (a.0 as f64 - b.0 as f64, a.1 as f64 - b.1 as f64, a.2 as f64 - b.2 as f64);
+
+    (2.0 + mean_r / 256.0) * dr * dr + 4.0 * dg * dg + (2.0 + (255.0 - mean_r) / 256.0) * db * db
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI.iter()
+        .min_by(|a, b| distance(rgb, a.1).total_cmp(&distance(rgb, b.1)))
+        .map(|&(color, _)| color)
+        .unwrap_or(Color::Reset)
+}
+
+// closest of the 6x6x6 color cube and the 24 step gray ramp
+fn nearest_256((r, g, b): (u8, u8, u8)) -> u8 {
+    let level = |v: u8| CUBE_LEVELS.iter().enumerate()
+        .min_by_key(|&(_, &level)| u8::abs_diff(level, v))
+        .map_or(0, |(ix, _)| ix);
+
+    let (cr, cg, cb) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[cr], CUBE_LEVELS[cg], CUBE_LEVELS[cb]);
+
+    let average = (r as usize + g as usize + b as usize) / 3;
+    let gray_ix = usize::min(average.saturating_sub(3) / 10, 23);
+    let gray = (8 + 10 * gray_ix) as u8;
+
+    if distance((r, g, b), (gray, gray, gray)) < distance((r, g, b), cube) {
+        232 + gray_ix as u8
+    } else {
+        16 + 36 * cr as u8 + 6 * cg as u8 + cb as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        let cases = [
+            // NO_COLOR, COLORTERM, TERM
+            ((None, None, None), ColorMode::None),
+            ((None, None, Some("")), ColorMode::None),
+            ((None, None, Some("dumb")), ColorMode::None),
+            ((None, None, Some("xterm")), ColorMode::Ansi16),
+            ((None, None, Some("linux")), ColorMode::Ansi16),
+            ((None, None, Some("xterm-256color")), ColorMode::Ansi256),
+            ((None, None, Some("screen-256color")), ColorMode::Ansi256),
+            ((None, None, Some("xterm-direct")), ColorMode::Truecolor),
+            ((None, Some("truecolor"), Some("xterm-256color")), ColorMode::Truecolor),
+            ((None, Some("24bit"), Some("xterm")), ColorMode::Truecolor),
+            ((None, Some("truecolor"), None), ColorMode::Truecolor),
+            ((None, Some("yes"), Some("xterm-256color")), ColorMode::Ansi256),
+            ((None, Some(""), Some("xterm")), ColorMode::Ansi16),
+            ((Some("1"), Some("truecolor"), Some("xterm-direct")), ColorMode::None),
+            ((Some("1"), None, Some("xterm-256color")), ColorMode::None),
+            // an empty NO_COLOR doesn't count
+            ((Some(""), Some("truecolor"), Some("xterm")), ColorMode::Truecolor),
+            ((Some(""), None, Some("xterm-256color")), ColorMode::Ansi256),
+        ];
+
+        for ((no_color, colorterm, term), expected) in cases {
+            assert_eq!(ColorMode::detect(no_color, colorterm, term), expected, "{:?} {:?} {:?}", no_color, colorterm, term);
+        }
+    }
+}
@@ -0,0 +1,631 @@
+use std::time::Duration;
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::prelude::{Color, Span, Style, Widget};
+use ratatui::widgets::{Block, Borders};
+use ratatui::widgets::canvas::{Canvas, Points};
+use crate::app::{Bloom, CanvasMarker, Settings, TICK_RATE};
+use crate::bonsai::{BonsaiTree, Edit, PointType};
+use crate::bonsai::growth::GrowthStyle;
+use crate::bonsai::seasons::Season;
+use crate::bonsai::utils::Point;
+use crate::gradient::{lerp_color, Gradient};
+use crate::grid::{DotGrid, Layer};
+use crate::palette::ColorMode;
+use crate::saved::SavedTree;
+use crate::weather::Weather;
+
+const BLOSSOM_COLOR: Color = Color::Rgb(255, 183, 197);
+
+// how much of black or white is mixed into the darkest and lightest shades
+const TRUNK_DARKEN: f64 = 0.6;
+const LEAF_DARKEN: f64 = 0.45;
+const LEAF_LIGHTEN: f64 = 0.3;
+
+// how far from a branch, in canvas units, a click still cuts or selects it
+const PRUNE_DISTANCE: f64 = 1.5;
+
+// longest stretch of time caught up at once, e.g. after the process was suspended
+const MAX_LAG: Duration = Duration::from_secs(1);
+
+// how long the tree takes to retract into the pot when quitting
+const WITHER_TIME: u64 = 1000;
+
+const ROTATE_STEP: f64 = std::f64::consts::PI / 36.0;
+const BEND_STEP: f64 = std::f64::consts::PI / 90.0;
+
+/// One bonsai with its pot, drawn on its own canvas in a part of the terminal.
+pub struct Plot<'a> {
+    tree: BonsaiTree,
+
+    // trunk and leaves, the layers that only grow or get cut and so are kept between frames
+    grid: DotGrid,
+
+    trunk_gradient: Gradient,
+
+    leaf_gradient: Gradient,
+
+    pot_points: Points<'a>,
+
+    bloom_points: Vec <(f64, f64)>,
+
+    bloom_style: Style,
+
+    bloom: Bloom,
+
+    // particles move every tick, so their points are kept in buffers refilled each frame
+    particles: Vec <(f64, f64)>,
+    particle_color: Color,
+
+    fading_particles: Vec <(f64, f64)>,
+    fading_color: Color,
+
+    weather_points: Vec <(f64, f64)>,
+
+    debris_points: Vec <(f64, f64)>,
+    debris_color: Color,
+
+    selection_points: Points <'a>,
+
+    // node selected for wiring, wire mode is off when empty
+    wiring: Option<usize>,
+
+    weather: Weather,
+
+    marker: CanvasMarker,
+
+    title: String,
+
+    bounds: (f64, f64),
+
+    area: Rect,
+
+    live: bool,
+
+    // wall time one growth step takes, and time not yet turned into steps
+    step_duration: Duration,
+    pending: Duration,
+
+    // stopped at a step of the growth picked with the arrow keys
+    paused: bool,
+
+    // branches can shrink while growing, so the drawn tree is rebuilt instead of extended
+    redraw_growth: bool,
+
+    show_particles: bool,
+
+    leaf_color: Color,
+
+    autumn_color: Color,
+}
+
+impl<'a> Plot<'a> {
+    pub fn new(area: Rect, settings: &Settings, theme: u16, seed: u64) -> Self {
+        let weather = settings.weather;
+
+        let bounds = (area.width as f64, area.height as f64);
+
+        let tree_color = match theme {
+            1 => Color::Rgb(205,133,63),
+            2 => Color::Rgb(144, 128, 90),
+            3 => Color::Rgb(224, 171, 61),
+            4 => Color::Rgb(80, 80, 200),
+            _ => Color::Rgb(205,133,63),
+        };
+
+        let leaf_color = match theme {
+            1 => Color::Green,
+            2 => Color::Rgb(220, 201, 201),
+            3 => Color::Rgb(236, 65, 32),
+            4 => Color::Rgb(173, 216, 230),
+            _ => Color::Green
+        };
+
+        // the nearest of 16 colors loses what a theme is about, so each has its own picks
+        let (tree_color, leaf_color) = match (settings.color, theme) {
+            (ColorMode::Ansi16, 2) => (Color::DarkGray, Color::LightMagenta),
+            (ColorMode::Ansi16, 3) => (Color::Yellow, Color::LightRed),
+            (ColorMode::Ansi16, 4) => (Color::Blue, Color::LightCyan),
+            (ColorMode::Ansi16, _) => (Color::Yellow, Color::Green),
+            _ => (tree_color, leaf_color),
+        };
+
+        let tree = grow_tree(area, settings, seed);
+
+        let step_duration = match settings.duration {
+            Some(secs) => Duration::try_from_secs_f64(secs).unwrap_or_default() / usize::max(tree.growth_steps(), 1) as u32,
+            None => Duration::from_millis(TICK_RATE),
+        };
+
+        let mut plot = Self {
+            tree,
+
+            grid: DotGrid::new((0, 0), bounds),
+
+            trunk_gradient: match settings.gradient {
+                true => Gradient::new(tree_color, TRUNK_DARKEN, 0.0),
+                false => Gradient::flat(tree_color),
+            },
+
+            leaf_gradient: match settings.gradient {
+                true => Gradient::new(leaf_color, LEAF_DARKEN, LEAF_LIGHTEN),
+                false => Gradient::flat(leaf_color),
+            },
+
+            pot_points: Points {
+                coords: &[],
+                color: Color::Magenta
+            },
+
+            bloom_points: Vec::new(),
+
+            bloom_style: Style::default().fg(match (settings.bloom, theme) {
+                (Bloom::Fruit, 3) => Color::Rgb(255, 140, 0),
+                (Bloom::Fruit, _) => Color::Rgb(220, 20, 60),
+                (_, 2) => Color::Rgb(255, 105, 180),
+                (_, 3) => Color::Rgb(255, 215, 0),
+                (_, 4) => Color::Rgb(238, 130, 238),
+                _ => Color::Rgb(255, 250, 250),
+            }),
+
+            bloom: settings.bloom,
+
+            particles: Vec::new(),
+            particle_color: match theme {
+                1 => Color::LightGreen,
+                2 => Color::Rgb(239, 169, 228),
+                3 => Color::Rgb(200, 7, 6),
+                4 => Color::Rgb(70, 130, 180),
+                _ => Color::LightGreen,
+            },
+
+            fading_particles: Vec::new(),
+            fading_color: match theme {
+                1 => Color::Rgb(85, 107, 47),
+                2 => Color::Rgb(150, 110, 140),
+                3 => Color::Rgb(120, 40, 20),
+                4 => Color::Rgb(50, 80, 110),
+                _ => Color::Rgb(85, 107, 47),
+            },
+
+            weather_points: Vec::new(),
+
+            debris_points: Vec::new(),
+            debris_color: tree_color,
+
+            selection_points: Points {
+                coords: &[],
+                color: Color::Yellow,
+            },
+
+            wiring: None,
+
+            weather: Weather::new(weather, bounds, seed),
+
+            marker: settings.marker,
+
+            title: String::from("Bonsai"),
+
+            bounds,
+
+            area,
+
+            live: settings.live,
+
+            step_duration,
+            pending: Duration::ZERO,
+
+            paused: false,
+
+            redraw_growth: settings.easing.overshoots(),
+
+            show_particles: settings.particles || settings.seasons.is_some(),
+
+            leaf_color,
+
+            autumn_color: match theme {
+                1 => Color::Rgb(218, 165, 32),
+                2 => Color::Rgb(205, 92, 92),
+                3 => Color::Rgb(139, 0, 0),
+                4 => Color::Rgb(255, 140, 0),
+                _ => Color::Rgb(218, 165, 32),
+            },
+        };
+
+        plot.set_resolution();
+        plot.pot_points.coords = Box::leak(plot.tree.get_pot().into_boxed_slice());
+        plot.weather.raise_surface(plot.pot_points.coords);
+
+        if !plot.live {
+            plot.refresh();
+
+            plot.weather.raise_surface(&plot.tree.get_tree());
+            plot.weather.raise_surface(&plot.tree.get_attached_leaves());
+        }
+
+        plot
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    fn canvas_title(&self) -> String {
+        match self.wiring {
+            Some(_) => format!("{} - wiring: arrows select, ,/. rotate, </> bend, Tab to finish", self.title),
+            None if self.paused => format!(
+                "{} - step {}/{}: arrows scrub, space plays",
+                self.title,
+                self.tree.current_step(),
+                self.tree.growth_steps(),
+            ),
+            None => self.title.clone(),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        // the terminal may have shrunk since the plot was laid out
+        let area = self.area.intersection(frame.size());
+
+        if !area.is_empty() {
+            frame.render_widget(self.tree_canvas(), area);
+        }
+    }
+
+    fn tree_canvas(&self) -> impl Widget + '_ {
+        Canvas::default()
+            .block(Block::default().borders(Borders::ALL).title(self.canvas_title()))
+            .marker(self.marker.marker())
+            .paint(|ctx| {
+                ctx.draw(&self.pot_points);
+                ctx.layer();
+                self.grid.draw(ctx);
+                ctx.layer();
+                if let Some(glyph) = self.bloom.glyph() {
+                    for &(x, y) in &self.bloom_points {
+                        ctx.print(x, y, Span::styled(glyph, self.bloom_style));
+                    }
+                }
+                ctx.layer();
+                ctx.draw(&Points { coords: &self.fading_particles, color: self.fading_color });
+                ctx.draw(&Points { coords: &self.particles, color: self.particle_color });
+                ctx.draw(&Points { coords: &self.debris_points, color: self.debris_color });
+                ctx.layer();
+                ctx.draw(&Points { coords: &self.weather_points, color: self.weather.kind().color() });
+                ctx.layer();
+                ctx.draw(&self.selection_points);
+            })
+            .x_bounds([0.0, self.bounds.0])
+            .y_bounds([0.0, self.bounds.1])
+    }
+
+    pub fn edits(&self) -> &[Edit] {
+        self.tree.edits()
+    }
+
+    /// Replays edits loaded with a saved tree.
+    pub fn apply_edits(&mut self, edits: &[Edit]) {
+        edits.iter().for_each(|&edit| self.tree.apply(edit));
+
+        self.refresh();
+    }
+
+    /// Advances the animation by as many steps as fit into the time `elapsed` since the
+    /// previous call, so a slow terminal drops frames instead of slowing the tree down.
+    /// A real time tree is instead advanced up to the wall clock.
+    pub fn advance(&mut self, elapsed: Duration, saved: Option<&mut SavedTree>) {
+        if let Some(saved) = saved {
+            let all_changes = saved.catch_up(&mut self.tree);
+            self.on_tick(all_changes);
+            return;
+        }
+
+        if self.paused && !self.tree.is_grown() {
+            self.weather_points = self.weather.step();
+            return;
+        }
+
+        // a step may take longer than the lag that is caught up, it still has to come due
+        self.pending = Duration::min(self.pending + elapsed, Duration::max(MAX_LAG, self.step_duration));
+
+        let mut all_changes: Vec<PointType> = Vec::new();
+        loop {
+            // once grown only particles are left, they move at the regular pace
+            let step = if self.tree.is_grown() { Duration::from_millis(TICK_RATE) } else { self.step_duration };
+            if self.pending < step {
+                break;
+            }
+
+            self.pending -= step;
+
+            all_changes.retain(|change| !change.is_transient());
+            all_changes.extend(self.tree.animation_step());
+        }
+
+        self.on_tick(all_changes);
+    }
+
+    fn on_tick(&mut self, all_changes: Vec<PointType>) {
+        if self.live {
+            let new_tree = filter(&all_changes, PointType::filter_tree);
+            self.weather.raise_surface(&new_tree);
+            if self.redraw_growth && !self.tree.is_grown() {
+                self.set_branches();
+            } else {
+                for p in new_tree {
+                    self.grid.insert(Layer::Trunk, p, self.trunk_gradient.color(p, self.trunk_shade(p)));
+                }
+            }
+
+            // how exposed a leaf is depends on its whole cluster, so the leaves are redrawn
+            let new_leaves = filter(&all_changes, PointType::filter_leaf);
+            self.weather.raise_surface(&new_leaves);
+            if !new_leaves.is_empty() {
+                self.set_leaves();
+            }
+
+            self.bloom_points.extend(filter(&all_changes, PointType::filter_bloom));
+        }
+
+        if let Some((season, progress)) = self.tree.season() {
+            self.leaf_gradient.set_color(match season {
+                Season::Summer => self.leaf_color,
+                Season::Autumn | Season::Winter => lerp_color(self.leaf_color, self.autumn_color, f64::min(1.0, 2.0 * progress)),
+                Season::Spring => lerp_color(BLOSSOM_COLOR, self.leaf_color, progress),
+            });
+
+            self.set_leaves();
+            self.bloom_points = self.tree.get_blooms();
+        }
+
+        if self.show_particles {
+            self.particles = filter(&all_changes, PointType::filter_particles);
+
+            self.fading_particles = filter(&all_changes, PointType::filter_fading);
+        }
+
+        self.debris_points = filter(&all_changes, PointType::filter_debris);
+
+        self.weather_points = self.weather.step();
+
+        self.grid.update();
+    }
+
+    /// Rebuilds the drawn tree from its current state, e.g. after it was pruned.
+    fn refresh(&mut self) {
+        self.set_branches();
+
+        self.set_leaves();
+
+        self.bloom_points = self.tree.get_blooms();
+
+        self.select(self.wiring);
+
+        self.grid.update();
+    }
+
+    fn set_branches(&mut self) {
+        self.grid.clear(Layer::Trunk);
+
+        for p in self.tree.get_tree() {
+            self.grid.insert(Layer::Trunk, p, self.trunk_gradient.color(p, self.trunk_shade(p)));
+        }
+    }
+
+    fn set_leaves(&mut self) {
+        self.grid.clear(Layer::Leaves);
+
+        // lighter on the outside of a cluster and towards the top of the tree
+        for (p, exposure) in self.tree.get_shaded_leaves() {
+            self.grid.insert(Layer::Leaves, p, self.leaf_gradient.color(p, 0.6 * exposure + 0.4 * p.1 / self.bounds.1));
+        }
+    }
+
+    // the trunk darkens towards the pot
+    fn trunk_shade(&self, (_, y): (f64, f64)) -> f64 {
+        y / self.bounds.1
+    }
+
+    /// Moves the growth by `steps` back or forth and holds it there.
+    pub fn scrub(&mut self, steps: isize) {
+        if !self.live {
+            return;
+        }
+
+        self.paused = true;
+        self.tree.seek(self.tree.current_step().saturating_add_signed(steps));
+        self.refresh();
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = self.live && !self.paused;
+    }
+
+    /// Plays the growth backwards as a closing effect, returns false once the tree is back in the pot.
+    pub fn wither(&mut self) -> bool {
+        let step = self.tree.current_step();
+        if step == 0 {
+            return false;
+        }
+
+        let stride = usize::max(1, (self.tree.growth_steps() * TICK_RATE as usize).div_ceil(WITHER_TIME as usize));
+
+        self.paused = true;
+        self.tree.seek(step.saturating_sub(stride));
+        self.refresh();
+
+        true
+    }
+
+    pub fn undo(&mut self) {
+        if self.tree.undo() {
+            self.refresh();
+        }
+    }
+
+    pub fn is_wiring(&self) -> bool {
+        self.wiring.is_some()
+    }
+
+    fn select(&mut self, node: Option<usize>) {
+        self.wiring = node;
+
+        self.selection_points.coords = match node {
+            Some(node) => Box::leak(self.tree.get_subtree(node).into_boxed_slice()),
+            None => &[],
+        };
+    }
+
+    pub fn toggle_wiring(&mut self) {
+        let node = match self.wiring {
+            Some(_) => None,
+            None => self.tree.children(0).first().copied(),
+        };
+
+        self.select(node);
+    }
+
+    pub fn on_wiring_key(&mut self, code: KeyCode) {
+        let Some(node) = self.wiring else { return };
+
+        let parent = self.tree.parent(node);
+        let siblings = self.tree.children(parent);
+        let position = siblings.iter().position(|&ix| ix == node).unwrap_or(0);
+
+        let previous = siblings[(position + siblings.len() - 1) % siblings.len()];
+        let next = siblings[(position + 1) % siblings.len()];
+        let child = self.tree.children(node).first().copied().unwrap_or(node);
+
+        match code {
+            KeyCode::Up => self.select(Some(child)),
+            KeyCode::Down if parent != 0 => self.select(Some(parent)),
+            KeyCode::Left => self.select(Some(previous)),
+            KeyCode::Right => self.select(Some(next)),
+            KeyCode::Char(',') => self.wire(Edit::Rotate { node, angle: ROTATE_STEP }),
+            KeyCode::Char('.') => self.wire(Edit::Rotate { node, angle: -ROTATE_STEP }),
+            KeyCode::Char('<') => self.wire(Edit::Bend { node, angle: BEND_STEP }),
+            KeyCode::Char('>') => self.wire(Edit::Bend { node, angle: -BEND_STEP }),
+            _ => {}
+        }
+    }
+
+    fn wire(&mut self, edit: Edit) {
+        self.tree.apply(edit);
+        self.refresh();
+    }
+
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        self.area.contains((column, row).into())
+    }
+
+    /// Maps a terminal cell back into canvas coordinates, the inverse of what the canvas does when drawing.
+    fn canvas_point(&self, column: u16, row: u16) -> Option<Point> {
+        let inner = Block::default().borders(Borders::ALL).inner(self.area);
+
+        if !inner.contains((column, row).into()) || inner.width < 2 || inner.height < 2 {
+            return None;
+        }
+
+        let x = (column - inner.x) as f64 * self.bounds.0 / (inner.width - 1) as f64;
+        let y = self.bounds.1 - (row - inner.y) as f64 * self.bounds.1 / (inner.height - 1) as f64;
+
+        Some(Point::from_floats(x, y))
+    }
+
+    pub fn select_at(&mut self, column: u16, row: u16) {
+        let Some(p) = self.canvas_point(column, row) else { return };
+
+        if let Some(node) = self.tree.nearest_branch(p, PRUNE_DISTANCE) {
+            self.select(Some(node));
+        }
+    }
+
+    pub fn prune_at(&mut self, column: u16, row: u16) {
+        let Some(p) = self.canvas_point(column, row) else { return };
+
+        if let Some(node) = self.tree.nearest_branch(p, PRUNE_DISTANCE) {
+            self.tree.prune(node);
+            self.refresh();
+        }
+    }
+
+    // one unit of the bounds is about a cell, the border takes the rest
+    fn set_resolution(&mut self) {
+        let inner = Block::default().borders(Borders::ALL).inner(self.area);
+        let (x, y) = self.marker.resolution();
+
+        self.grid = DotGrid::new(((inner.width * x) as usize, (inner.height * y) as usize), self.bounds);
+
+        self.tree.set_resolution(
+            x as f64 * inner.width as f64 / self.bounds.0,
+            y as f64 * inner.height as f64 / self.bounds.1,
+        );
+    }
+
+    pub fn cycle_marker(&mut self) {
+        self.set_marker(self.marker.next());
+    }
+
+    fn set_marker(&mut self, marker: CanvasMarker) {
+        self.marker = marker;
+
+        self.set_resolution();
+        self.pot_points.coords = Box::leak(self.tree.get_pot().into_boxed_slice());
+        self.refresh();
+    }
+
+    pub fn cycle_weather(&mut self) {
+        self.weather.cycle();
+    }
+
+    pub fn intensify_weather(&mut self) {
+        self.weather.intensify();
+    }
+
+    pub fn calm_weather(&mut self) {
+        self.weather.calm();
+    }
+
+    /// Takes over the marker and weather picked with the keys in `other`, e.g. the plot of
+    /// the seed browsed before.
+    pub fn keep_view(&mut self, other: &Plot) {
+        self.weather.adopt(&other.weather);
+
+        if self.marker != other.marker {
+            self.set_marker(other.marker);
+        }
+    }
+}
+
+/// Generates the tree for a plot of the given size, also used to measure real time trees.
+pub fn grow_tree(area: Rect, settings: &Settings, seed: u64) -> BonsaiTree {
+    let size = Rect::new(0, 0, area.width, area.height);
+    let year = settings.seasons.map(|secs| u32::try_from(secs as u64 * 1000 / TICK_RATE).unwrap_or(u32::MAX));
+    let bloom_density = if settings.bloom == Bloom::None { 0.0 } else { settings.bloom_density };
+
+    let style = GrowthStyle {
+        easing: settings.easing,
+        order: settings.growth_order,
+        reveal: settings.leaf_reveal,
+    };
+
+    let mut tree = BonsaiTree::new(size, Some(seed), settings.live, year, bloom_density, style);
+    tree.set_roots(settings.roots);
+
+    tree.generate();
+    tree.normalize();
+
+    tree
+}
+
+fn filter<F>(changes: &[PointType], f: F) -> Vec <(f64, f64)>
+    where F: FnMut(&PointType) -> Option<(f64, f64)> {
+        changes.iter()
+            .filter_map(f)
+            .collect()
+}
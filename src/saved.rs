@@ -0,0 +1,304 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::{Bloom, Settings};
+use crate::bonsai::{BonsaiTree, Edit, PointType};
+
+const DAY: u64 = 24 * 60 * 60;
+const GROWTH_DAYS: u64 = 14;
+
+// trees saved before the generator was pinned have no version and would grow differently now
+const VERSION: u32 = 1;
+
+/// Tree persisted between runs together with the user's edits. A tree planted
+/// with `--grow-realtime` also keeps growing with the wall clock time since planting.
+#[derive(Serialize, Deserialize)]
+pub struct SavedTree {
+    #[serde(default)]
+    version: u32,
+
+    pub seed: u64,
+    pub theme: u16,
+    pub bloom: Bloom,
+    pub bloom_density: f64,
+
+    #[serde(default)]
+    pub edits: Vec <Edit>,
+
+    planted: u64,
+    // animation steps already shown, never goes back even if the clock does
+    steps: usize,
+
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    replayed: usize,
+    #[serde(skip)]
+    total_steps: usize,
+    #[serde(skip)]
+    saved_steps: usize,
+}
+
+impl SavedTree {
+    pub fn load_or_plant(path: PathBuf, settings: &Settings) -> io::Result<Self> {
+        match fs::read_to_string(&path) {
+            Ok(json) => Self::parse(&json, path),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let saved = SavedTree {
+                    version: VERSION,
+
+                    seed: settings.seed.unwrap_or_else(rand::random),
+                    theme: settings.theme,
+                    bloom: settings.bloom,
+                    bloom_density: settings.bloom_density,
+
+                    edits: Vec::new(),
+
+                    planted: now(),
+                    steps: 0,
+
+                    path,
+
+                    replayed: 0,
+                    total_steps: 0,
+                    saved_steps: 0,
+                };
+                saved.save()?;
+
+                Ok(saved)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn parse(json: &str, path: PathBuf) -> io::Result<Self> {
+        let saved: SavedTree = serde_json::from_str(json).map_err(io::Error::other)?;
+
+        if saved.version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} was saved in format version {}, this rustbonsai only grows trees saved in version {}",
+                    path.display(), saved.version, VERSION,
+                ),
+            ));
+        }
+
+        Ok(SavedTree { path, saved_steps: saved.steps, ..saved })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&self.path, serde_json::to_string_pretty(self).map_err(io::Error::other)?)
+    }
+
+    /// Saves the tree if it grew since it was loaded or last saved here, so the growth
+    /// shown isn't lost if the program doesn't get to exit cleanly.
+    pub fn save_progress(&mut self) -> io::Result<()> {
+        if self.steps > self.saved_steps {
+            self.save()?;
+            self.saved_steps = self.steps;
+        }
+
+        Ok(())
+    }
+
+    pub fn age_days(&self) -> u64 {
+        now().saturating_sub(self.planted) / DAY
+    }
+
+    /// Measures how many animation steps growing the freshly generated `tree` takes.
+    pub fn measure(&mut self, tree: &BonsaiTree) {
+        self.total_steps = tree.growth_steps();
+    }
+
+    fn due_steps(&self) -> usize {
+        let elapsed = now().saturating_sub(self.planted);
+        let due = (self.total_steps as u128 * elapsed as u128 / (GROWTH_DAYS * DAY) as u128) as usize;
+
+        usize::max(self.steps, usize::min(due, self.total_steps))
+    }
+
+    /// Advances the animation to the step matching the current time, replaying
+    /// everything grown since planting on the first call. Particles keep moving while
+    /// the next step isn't due yet.
+    pub fn catch_up(&mut self, tree: &mut BonsaiTree) -> Vec<PointType> {
+        if self.replayed >= self.total_steps {
+            return tree.animation_step();
+        }
+
+        let due = self.due_steps();
+        if self.replayed >= due {
+            return tree.particle_step();
+        }
+
+        let mut result = Vec::new();
+        while self.replayed < due {
+            result.extend(tree.animation_step());
+            self.replayed += 1;
+        }
+
+        self.steps = due;
+
+        result
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Where the `--grow-realtime` tree lives unless `--save` points elsewhere.
+pub fn realtime_path() -> io::Result<PathBuf> {
+    Ok(data_dir()?.join("tree.json"))
+}
+
+/// Where `--browse` keeps the favorite seeds unless `--favorites` points elsewhere.
+pub fn favorites_path() -> io::Result<PathBuf> {
+    Ok(data_dir()?.join("favorites.txt"))
+}
+
+fn data_dir() -> io::Result<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "neither XDG_DATA_HOME nor HOME is set"))?;
+
+    Ok(data_home.join("rustbonsai"))
+}
+
+/// Seeds marked while browsing, one per line so they can be handed back to `--seed`.
+/// Lines that aren't seeds, e.g. notes added by hand, are kept as they are.
+pub struct Favorites {
+    path: PathBuf,
+    lines: Vec <String>,
+}
+
+impl Favorites {
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let lines = match fs::read_to_string(&path) {
+            Ok(text) => text.lines().map(String::from).collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Favorites { path, lines })
+    }
+
+    fn is_seed(line: &str, seed: u64) -> bool {
+        line.trim().parse() == Ok(seed)
+    }
+
+    pub fn contains(&self, seed: u64) -> bool {
+        self.lines.iter().any(|line| Self::is_seed(line, seed))
+    }
+
+    /// Marks the seed as a favorite or unmarks it if it already was one, and saves the file.
+    pub fn toggle(&mut self, seed: u64) -> io::Result<()> {
+        if self.contains(seed) {
+            self.lines.retain(|line| !Self::is_seed(line, seed));
+        } else {
+            self.lines.push(seed.to_string());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&self.path, self.lines.iter().map(|line| format!("{}\n", line)).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fresh directory per test, tests run in parallel
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rustbonsai-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+
+        dir
+    }
+
+    #[test]
+    fn saves_without_a_version_are_rejected() {
+        let json = r#"{ "seed": 42, "theme": 1, "bloom": "None", "bloom_density": 0.05, "planted": 0, "steps": 0 }"#;
+        let error = SavedTree::parse(json, PathBuf::from("tree.json")).err().unwrap();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("tree.json was saved in format version 0"));
+
+        let json = json.replace("{", r#"{ "version": 1,"#);
+        let saved = SavedTree::parse(&json, PathBuf::from("tree.json")).unwrap();
+
+        assert_eq!(saved.seed, 42);
+    }
+
+    #[test]
+    fn missing_favorites_are_empty() {
+        let dir = temp_dir("missing");
+        let favorites = Favorites::load(dir.join("favorites.txt")).unwrap();
+
+        assert!(!favorites.contains(42));
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn favorites_load_seeds() {
+        let dir = temp_dir("load");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("favorites.txt"), "42\n 7 \nnot a seed\n").unwrap();
+
+        let favorites = Favorites::load(dir.join("favorites.txt")).unwrap();
+
+        assert!(favorites.contains(42));
+        assert!(favorites.contains(7));
+        assert!(!favorites.contains(8));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn toggle_keeps_other_lines() {
+        let dir = temp_dir("toggle");
+        let path = dir.join("favorites.txt");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "# the good ones\n42\nmaple 7\n").unwrap();
+
+        let mut favorites = Favorites::load(path.clone()).unwrap();
+
+        favorites.toggle(9).unwrap();
+        assert!(favorites.contains(9));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# the good ones\n42\nmaple 7\n9\n");
+
+        favorites.toggle(42).unwrap();
+        assert!(!favorites.contains(42));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# the good ones\nmaple 7\n9\n");
+
+        assert!(Favorites::load(path).unwrap().contains(9));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn toggle_creates_the_file() {
+        let dir = temp_dir("create");
+        let mut favorites = Favorites::load(dir.join("favorites.txt")).unwrap();
+
+        favorites.toggle(42).unwrap();
+
+        assert_eq!(fs::read_to_string(dir.join("favorites.txt")).unwrap(), "42\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
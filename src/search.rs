@@ -0,0 +1,112 @@
+use std::io;
+use std::ops::Range;
+
+use clap::ValueEnum;
+use rayon::prelude::*;
+use ratatui::backend::TestBackend;
+use ratatui::layout::Rect;
+use ratatui::Terminal;
+
+use crate::app::{CanvasMarker, Settings};
+use crate::palette::ColorMode;
+use crate::plot::Plot;
+use crate::stats::TreeStats;
+
+/// Side the found trees should lean to.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lean {
+    Left,
+    Right,
+    /// Upright and as symmetric as possible.
+    Straight,
+}
+
+/// What a tree has to look like to be found, a limit left at `None` isn't checked.
+/// Widths are shares of the plot's width, ratios the tree's width divided by its height.
+#[derive(Debug, Clone, Default)]
+pub struct Criteria {
+    pub min_leaves: Option<usize>,
+    pub max_leaves: Option<usize>,
+    pub min_branches: Option<usize>,
+    pub max_branches: Option<usize>,
+    pub min_width: Option<f64>,
+    pub max_width: Option<f64>,
+    pub min_ratio: Option<f64>,
+    pub max_ratio: Option<f64>,
+    pub min_symmetry: Option<f64>,
+
+    pub lean: Option<Lean>,
+}
+
+impl Criteria {
+    pub fn accepts(&self, stats: &TreeStats) -> bool {
+        fn within<T: PartialOrd>(value: T, min: Option<T>, max: Option<T>) -> bool {
+            min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+        }
+
+        within(stats.leaves, self.min_leaves, self.max_leaves)
+            && within(stats.branches(), self.min_branches, self.max_branches)
+            && within(stats.width(), self.min_width, self.max_width)
+            && within(stats.aspect_ratio(), self.min_ratio, self.max_ratio)
+            && within(stats.symmetry, self.min_symmetry, None)
+    }
+
+    /// Higher for trees that fit better, trees are otherwise ranked by their symmetry.
+    pub fn score(&self, stats: &TreeStats) -> f64 {
+        match self.lean {
+            Some(Lean::Left) => -stats.lean,
+            Some(Lean::Right) => stats.lean,
+            Some(Lean::Straight) => stats.symmetry - f64::abs(stats.lean),
+            None => stats.symmetry,
+        }
+    }
+}
+
+/// Grows the trees of all `seeds` in a plot of `size` on every core and returns the `count`
+/// that fit `criteria` best, best first.
+pub fn search(criteria: &Criteria, seeds: Range<u64>, size: (u16, u16), count: usize) -> Vec <TreeStats> {
+    let mut found: Vec <(f64, TreeStats)> = seeds.into_par_iter()
+        .map(|seed| TreeStats::new(seed, size))
+        .filter(|stats| criteria.accepts(stats))
+        .map(|stats| (criteria.score(&stats), stats))
+        .collect();
+
+    found.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    found.into_iter()
+        .take(count)
+        .map(|(_, stats)| stats)
+        .collect()
+}
+
+/// Draws the trees of `seeds` in plots of `thumbnail` cells, `columns` of them side by side,
+/// and returns the lines of text they make up.
+pub fn gallery(seeds: &[u64], thumbnail: (u16, u16), columns: u16) -> io::Result<Vec <String>> {
+    let columns = u16::max(columns, 1);
+    let rows = (seeds.len() as u16).div_ceil(columns);
+
+    let settings = Settings { gradient: false, color: ColorMode::None, marker: CanvasMarker::Braille, ..Settings::default() };
+
+    let plots: Vec <Plot> = seeds.iter().enumerate()
+        .map(|(ix, &seed)| {
+            let (column, row) = (ix as u16 % columns, ix as u16 / columns);
+            let area = Rect::new(column * thumbnail.0, row * thumbnail.1, thumbnail.0, thumbnail.1);
+
+            let mut plot = Plot::new(area, &settings, settings.theme, seed);
+            plot.set_title(format!("seed {}", seed));
+
+            plot
+        })
+        .collect();
+
+    let mut terminal = Terminal::new(TestBackend::new(columns * thumbnail.0, rows * thumbnail.1))?;
+    terminal.draw(|frame| plots.iter().for_each(|plot| plot.render(frame)))?;
+
+    let buffer = terminal.backend().buffer();
+    Ok((0..buffer.area.height)
+        .map(|y| {
+            let line: String = (0..buffer.area.width).map(|x| buffer.get(x, y).symbol()).collect();
+            line.trim_end().to_string()
+        })
+        .collect())
+}
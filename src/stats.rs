@@ -0,0 +1,148 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use ratatui::layout::Rect;
+use serde::Serialize;
+
+use crate::bonsai::BonsaiTree;
+use crate::bonsai::growth::GrowthStyle;
+
+/// Smallest box around the trunk and leaves, in the cells of the plot with y going up.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct BoundingBox {
+    pub min: (f64, f64),
+    pub max: (f64, f64),
+}
+
+/// Shape of the tree grown from a seed, to compare seeds without watching them grow.
+#[derive(Serialize, Debug)]
+pub struct TreeStats {
+    pub seed: u64,
+    pub size: (u16, u16),
+
+    pub nodes: usize,
+    pub leaves: usize,
+
+    // how many nodes are that many parent hops away from the root, starting with the root itself
+    pub nodes_per_depth: Vec <usize>,
+    pub nodes_per_tier: BTreeMap <u32, usize>,
+    pub branches_per_tier: BTreeMap <u32, usize>,
+
+    pub bounding_box: BoundingBox,
+    // from -1, the whole tree left of the trunk's base, to 1 all of it right
+    pub lean: f64,
+    // from 0 to 1, how much of the tree has its mirror image across the middle of the bounding box, lean aside
+    pub symmetry: f64,
+
+    // nodes and leaves the animation grows, and the frames of the recorded growth
+    pub animation_steps: usize,
+    pub timeline_frames: usize,
+}
+
+impl TreeStats {
+    /// Grows the tree of `seed` in a plot of `size` and measures it.
+    pub fn new(seed: u64, size: (u16, u16)) -> Self {
+        let mut tree = BonsaiTree::new(Rect::new(0, 0, size.0, size.1), Some(seed), false, None, 0.0, GrowthStyle::default());
+        tree.generate();
+        tree.normalize();
+
+        let mut depths: Vec <usize> = Vec::with_capacity(tree.node_count());
+        for node in 0..tree.node_count() {
+            // parents are always pushed before their children
+            let depth = if node == 0 { 0 } else { depths[tree.parent(node)] + 1 };
+            depths.push(depth);
+        }
+
+        let mut nodes_per_depth = vec![0; depths.iter().max().map_or(0, |&depth| depth + 1)];
+        depths.iter().for_each(|&depth| nodes_per_depth[depth] += 1);
+
+        let mut nodes_per_tier = BTreeMap::new();
+        (0..tree.node_count()).for_each(|node| *nodes_per_tier.entry(tree.tier(node)).or_insert(0) += 1);
+
+        let mut branches_per_tier = BTreeMap::new();
+        tree.branch_starts().iter().for_each(|&node| *branches_per_tier.entry(tree.tier(node)).or_insert(0) += 1);
+
+        let leaves = tree.get_leaves();
+        let points: Vec <(f64, f64)> = tree.get_tree().into_iter().chain(leaves.iter().copied()).collect();
+
+        let bounding_box = BoundingBox {
+            min: points.iter().fold((f64::MAX, f64::MAX), |a, &p| (f64::min(a.0, p.0), f64::min(a.1, p.1))),
+            max: points.iter().fold((f64::MIN, f64::MIN), |a, &p| (f64::max(a.0, p.0), f64::max(a.1, p.1))),
+        };
+
+        // the tree is centered on its base, see `BonsaiTree::normalize`
+        let base = size.0 as f64 / 2.0;
+
+        let lean = points.iter().map(|&(x, _)| x - base).sum::<f64>() / f64::max(points.len() as f64 * base, 1.0);
+
+        let mut columns: HashMap <i64, usize> = HashMap::new();
+        points.iter().for_each(|&(x, _)| *columns.entry(x.round() as i64).or_insert(0) += 1);
+
+        let middle = bounding_box.min.0 + bounding_box.max.0;
+        let mirrored: usize = columns.iter()
+            .map(|(&x, &count)| usize::min(count, columns.get(&((middle - x as f64).round() as i64)).copied().unwrap_or(0)))
+            .sum();
+        let symmetry = mirrored as f64 / usize::max(points.len(), 1) as f64;
+
+        TreeStats {
+            seed,
+            size,
+
+            nodes: tree.node_count(),
+            leaves: leaves.len(),
+
+            nodes_per_depth,
+            nodes_per_tier,
+            branches_per_tier,
+
+            bounding_box,
+            lean,
+            symmetry,
+
+            animation_steps: tree.animation_size(),
+            timeline_frames: tree.growth_steps(),
+        }
+    }
+
+    pub fn branches(&self) -> usize {
+        self.branches_per_tier.values().sum()
+    }
+
+    /// Width of the tree as a share of the width of the plot.
+    pub fn width(&self) -> f64 {
+        (self.bounding_box.max.0 - self.bounding_box.min.0) / f64::max(self.size.0 as f64, 1.0)
+    }
+
+    /// Width of the tree divided by its height.
+    pub fn aspect_ratio(&self) -> f64 {
+        (self.bounding_box.max.0 - self.bounding_box.min.0) / f64::max(self.bounding_box.max.1 - self.bounding_box.min.1, 1.0)
+    }
+}
+
+impl fmt::Display for TreeStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let per_tier = |counts: &BTreeMap <u32, usize>| counts.iter().rev()
+            .map(|(tier, count)| format!("{}: {}", tier, count))
+            .collect::<Vec <_>>()
+            .join(", ");
+
+        let per_depth = self.nodes_per_depth.iter().map(usize::to_string).collect::<Vec <_>>().join(" ");
+
+        writeln!(f, "seed:              {}", self.seed)?;
+        writeln!(f, "plot:              {}x{}", self.size.0, self.size.1)?;
+        writeln!(f, "nodes:             {}", self.nodes)?;
+        writeln!(f, "leaves:            {}", self.leaves)?;
+        writeln!(f, "nodes per depth:   {}", per_depth)?;
+        writeln!(f, "nodes per tier:    {}", per_tier(&self.nodes_per_tier))?;
+        writeln!(f, "branches per tier: {}", per_tier(&self.branches_per_tier))?;
+        writeln!(
+            f,
+            "bounding box:      x {:.1}..{:.1}, y {:.1}..{:.1}",
+            self.bounding_box.min.0, self.bounding_box.max.0, self.bounding_box.min.1, self.bounding_box.max.1,
+        )?;
+        writeln!(f, "lean:              {:.2}", self.lean)?;
+        writeln!(f, "symmetry:          {:.2}", self.symmetry)?;
+        writeln!(f, "animation steps:   {}", self.animation_steps)?;
+        write!(f, "timeline frames:   {}", self.timeline_frames)
+    }
+}
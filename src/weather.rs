@@ -0,0 +1,335 @@
+use std::collections::VecDeque;
+
+use clap::ValueEnum;
+use rand::Rng;
+use ratatui::style::Color;
+
+use crate::bonsai::seed::{self, TreeRng};
+use crate::bonsai::utils::Point;
+
+const RAIN_SPEED: f64 = 1.2;
+const RAIN_SLANT: f64 = 0.2;
+const RAIN_STREAK: usize = 3;
+
+const SNOW_SPEED: f64 = 0.15;
+const SNOW_DRIFT: f64 = 0.15;
+const SNOW_STEP: f64 = 0.2;
+const MAX_SETTLED: usize = 1500;
+
+const FOG_BANDS: usize = 3;
+const FOG_DRIFT: f64 = 0.05;
+
+const MAX_INTENSITY: u8 = 5;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Snow,
+    Fog,
+}
+
+impl WeatherKind {
+    fn next(self) -> Self {
+        match self {
+            WeatherKind::Clear => WeatherKind::Rain,
+            WeatherKind::Rain => WeatherKind::Snow,
+            WeatherKind::Snow => WeatherKind::Fog,
+            WeatherKind::Fog => WeatherKind::Clear,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            WeatherKind::Clear => Color::Reset,
+            WeatherKind::Rain => Color::Rgb(100, 149, 237),
+            WeatherKind::Snow => Color::White,
+            WeatherKind::Fog => Color::DarkGray,
+        }
+    }
+}
+
+pub struct Weather {
+    kind: WeatherKind,
+    intensity: u8,
+
+    rng: TreeRng,
+
+    bounds: (f64, f64),
+
+    // highest tree, leaf or pot point in every canvas column
+    surface: Vec <f64>,
+    snow_pile: Vec <f64>,
+
+    drops: Vec <Point>,
+    settled: VecDeque <Point>,
+
+    fog_offset: f64,
+}
+
+impl Weather {
+    /// Weather over a plot of `bounds`, the same `seed` brings the same drops and flakes.
+    pub fn new(kind: WeatherKind, bounds: (f64, f64), seed: u64) -> Self {
+        let columns = bounds.0 as usize + 1;
+
+        Weather {
+            kind,
+            intensity: 2,
+
+            rng: seed::rng(seed),
+
+            bounds,
+
+            surface: vec![0.0; columns],
+            snow_pile: vec![0.0; columns],
+
+            drops: Vec::new(),
+            settled: VecDeque::new(),
+
+            fog_offset: 0.0,
+        }
+    }
+
+    pub fn kind(&self) -> WeatherKind {
+        self.kind
+    }
+
+    pub fn cycle(&mut self) {
+        self.set_kind(self.kind.next());
+    }
+
+    fn set_kind(&mut self, kind: WeatherKind) {
+        self.kind = kind;
+
+        self.drops.clear();
+        self.settled.clear();
+        self.snow_pile.iter_mut().for_each(|h| *h = 0.0);
+    }
+
+    pub fn intensify(&mut self) {
+        self.intensity = u8::min(self.intensity + 1, MAX_INTENSITY);
+    }
+
+    pub fn calm(&mut self) {
+        self.intensity = u8::max(self.intensity - 1, 1);
+    }
+
+    /// Switches to the kind and intensity of `other`.
+    pub fn adopt(&mut self, other: &Weather) {
+        if self.kind != other.kind {
+            self.set_kind(other.kind);
+        }
+
+        self.intensity = other.intensity;
+    }
+
+    /// Registers points the weather can land on, e.g. newly grown branches and leaves.
+    pub fn raise_surface(&mut self, coords: &[(f64, f64)]) {
+        for &(x, y) in coords {
+            if let Some(h) = self.surface.get_mut(x as usize) {
+                *h = f64::max(*h, y);
+            }
+        }
+    }
+
+    fn column(&self, x: f64) -> Option<usize> {
+        if x >= 0.0 && (x as usize) < self.surface.len() { Some(x as usize) } else { None }
+    }
+
+    pub fn step(&mut self) -> Vec <(f64, f64)> {
+        match self.kind {
+            WeatherKind::Clear => Vec::new(),
+            WeatherKind::Rain => self.step_rain(),
+            WeatherKind::Snow => self.step_snow(),
+            WeatherKind::Fog => self.step_fog(),
+        }
+    }
+
+    fn spawn(&mut self, count: usize) {
+        for _ in 0..count {
+            let x = self.rng.gen_range(0.0..self.bounds.0);
+            self.drops.push(Point::from_floats(x, self.bounds.1));
+        }
+    }
+
+    fn step_rain(&mut self) -> Vec <(f64, f64)> {
+        self.spawn(2 * self.intensity as usize);
+
+        let velocity = Point::from_floats(-RAIN_SLANT, -RAIN_SPEED);
+
+        let mut result = Vec::new();
+        let mut next_drops = Vec::new();
+
+        for &drop in &self.drops {
+            let p = drop + velocity;
+
+            match self.column(p.x) {
+                Some(col) if p.y > self.surface[col] => {
+                    result.extend((0..RAIN_STREAK).map(|i| (p.x - velocity.x * 0.5 * i as f64, p.y + 0.5 * i as f64)));
+                    next_drops.push(p);
+                }
+                _ => (),
+            }
+        }
+
+        self.drops = next_drops;
+
+        result
+    }
+
+    fn step_snow(&mut self) -> Vec <(f64, f64)> {
+        if self.rng.gen_range(0..MAX_INTENSITY) < self.intensity {
+            self.spawn(1);
+        }
+
+        let mut result = Vec::new();
+        let mut next_drops = Vec::new();
+
+        for i in 0..self.drops.len() {
+            let drift = self.rng.gen_range(-SNOW_DRIFT..=SNOW_DRIFT);
+            let p = self.drops[i] + Point::from_floats(drift, -SNOW_SPEED);
+
+            let Some(col) = self.column(p.x) else { continue };
+
+            let rest = self.surface[col] + self.snow_pile[col];
+            if p.y <= rest {
+                self.settle(Point::from_floats(p.x, rest), col);
+            } else {
+                result.push((p.x, p.y));
+                next_drops.push(p);
+            }
+        }
+
+        self.drops = next_drops;
+
+        result.extend(self.settled.iter().map(|p| (p.x, p.y)));
+
+        result
+    }
+
+    fn settle(&mut self, p: Point, col: usize) {
+        self.settled.push_back(p);
+        self.snow_pile[col] += SNOW_STEP;
+
+        if self.settled.len() > MAX_SETTLED {
+            if let Some(melted) = self.settled.pop_front() {
+                let pile = &mut self.snow_pile[melted.x as usize];
+                *pile = f64::max(0.0, *pile - SNOW_STEP);
+            }
+        }
+    }
+
+    fn step_fog(&mut self) -> Vec <(f64, f64)> {
+        self.fog_offset += FOG_DRIFT;
+
+        let density = self.intensity as f64 / MAX_INTENSITY as f64;
+        let band_width = self.bounds.1 / (3 * FOG_BANDS) as f64;
+
+        let mut result = Vec::new();
+
+        for y in 0..=self.bounds.1 as usize {
+            let band = (0..FOG_BANDS)
+                .map(|b| (b as f64 + 0.5) * self.bounds.1 / FOG_BANDS as f64)
+                .map(|center| f64::exp(-f64::powi((y as f64 - center) / band_width, 2)))
+                .fold(0.0, f64::max);
+
+            for x in 0..=self.bounds.0 as usize {
+                if noise(x as f64 + self.fog_offset, y as f64) < band * density {
+                    result.push((x as f64, y as f64));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+// cheap hash based value noise in [0, 1), smooth along x so the fog drifts instead of flickering
+fn noise(x: f64, y: f64) -> f64 {
+    let hash = |x: f64| f64::fract(f64::abs(f64::sin(x * 12.9898 + y * 78.233) * 43758.5453));
+
+    let t = f64::fract(x);
+    let t = t * t * (3.0 - 2.0 * t);
+
+    hash(f64::floor(x)) * (1.0 - t) + hash(f64::floor(x) + 1.0) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: (f64, f64) = (60.0, 24.0);
+
+    fn steps(weather: &mut Weather, count: usize) -> Vec <(f64, f64)> {
+        (0..count).fold(Vec::new(), |_, _| weather.step())
+    }
+
+    fn within(points: &[(f64, f64)]) -> bool {
+        points.iter().all(|&(x, y)| (0.0..BOUNDS.0 + 1.0).contains(&x) && (0.0..=BOUNDS.1 + 1.0).contains(&y))
+    }
+
+    #[test]
+    fn clear_draws_nothing() {
+        let mut weather = Weather::new(WeatherKind::Clear, BOUNDS, 42);
+
+        assert!(steps(&mut weather, 100).is_empty());
+    }
+
+    #[test]
+    fn rain_stops_at_the_surface() {
+        let mut weather = Weather::new(WeatherKind::Rain, BOUNDS, 42);
+        weather.raise_surface(&(0..=60).map(|x| (x as f64, 10.0)).collect::<Vec <_>>());
+
+        let points = steps(&mut weather, 100);
+
+        assert!(!points.is_empty());
+        assert!(within(&points));
+        assert!(weather.drops.iter().all(|drop| drop.y > 10.0));
+    }
+
+    #[test]
+    fn snow_settles_on_the_surface() {
+        let mut weather = Weather::new(WeatherKind::Snow, BOUNDS, 42);
+        weather.raise_surface(&(0..=60).map(|x| (x as f64, 5.0)).collect::<Vec <_>>());
+
+        let points = steps(&mut weather, 2000);
+
+        assert!(within(&points));
+        assert!(!weather.settled.is_empty());
+        assert!(weather.settled.len() <= MAX_SETTLED);
+        assert!(weather.settled.iter().all(|flake| flake.y >= 5.0));
+    }
+
+    #[test]
+    fn fog_thickens_with_intensity() {
+        let mut thin = Weather::new(WeatherKind::Fog, BOUNDS, 42);
+        thin.calm();
+
+        let mut thick = Weather::new(WeatherKind::Fog, BOUNDS, 42);
+        (0..MAX_INTENSITY).for_each(|_| thick.intensify());
+
+        let (thin, thick) = (thin.step(), thick.step());
+
+        assert!(within(&thick));
+        assert!(!thin.is_empty());
+        assert!(thin.len() < thick.len());
+    }
+
+    #[test]
+    fn same_seed_same_weather() {
+        for kind in [WeatherKind::Rain, WeatherKind::Snow] {
+            let mut a = Weather::new(kind, BOUNDS, 7);
+            let mut b = Weather::new(kind, BOUNDS, 7);
+
+            assert_eq!(steps(&mut a, 50), steps(&mut b, 50));
+        }
+    }
+
+    #[test]
+    fn cycle_goes_through_every_kind() {
+        let mut weather = Weather::new(WeatherKind::Clear, BOUNDS, 42);
+        let kinds: Vec <WeatherKind> = (0..4).map(|_| { weather.cycle(); weather.kind() }).collect();
+
+        assert_eq!(kinds, [WeatherKind::Rain, WeatherKind::Snow, WeatherKind::Fog, WeatherKind::Clear]);
+    }
+}
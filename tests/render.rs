@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use ratatui::backend::TestBackend;
+use ratatui::layout::Rect;
+use ratatui::Terminal;
+use rustbonsai::app::{App, CanvasMarker, Settings, TICK_RATE};
+use rustbonsai::error::BonsaiError;
+use rustbonsai::palette::ColorMode;
+
+const SEED: u64 = 42;
+const SIZE: (u16, u16) = (60, 24);
+
+fn settings(theme: u16, live: bool) -> Settings {
+    Settings { seed: Some(SEED), live, theme, color: ColorMode::Truecolor, ..Settings::default() }
+}
+
+fn render(app: &App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(SIZE.0, SIZE.1)).unwrap();
+    terminal.draw(|frame| app.ui(frame)).unwrap();
+
+    format!("{:?}", terminal.backend().buffer())
+}
+
+fn app(settings: &Settings) -> App<'static> {
+    App::new(settings, Rect::new(0, 0, SIZE.0, SIZE.1)).unwrap()
+}
+
+#[test]
+fn themes() {
+    for theme in 1..=4 {
+        insta::assert_snapshot!(format!("theme_{theme}"), render(&app(&settings(theme, false))));
+    }
+}
+
+#[test]
+fn live_growth() {
+    let mut app = app(&settings(1, true));
+    let mut ticks = 0;
+
+    for until in [0, 10, 40, 120] {
+        while ticks < until {
+            app.tick(Duration::from_millis(TICK_RATE), None);
+            ticks += 1;
+        }
+
+        insta::assert_snapshot!(format!("live_tick_{until}"), render(&app));
+    }
+}
+
+#[test]
+fn markers() {
+    for marker in [CanvasMarker::Braille, CanvasMarker::HalfBlock] {
+        let settings = Settings { marker, ..settings(1, false) };
+
+        insta::assert_snapshot!(format!("marker_{marker:?}").to_lowercase(), render(&app(&settings)));
+    }
+}
+
+#[test]
+fn roots() {
+    let mut app = app(&Settings { roots: true, ..settings(1, true) });
+    for _ in 0..5 {
+        app.tick(Duration::from_millis(TICK_RATE), None);
+    }
+
+    insta::assert_snapshot!("roots_growing", render(&app));
+    insta::assert_snapshot!("roots_grown", render(&self::app(&Settings { roots: true, ..settings(1, false) })));
+}
+
+#[test]
+fn sixteen_colors() {
+    let settings = Settings { color: ColorMode::Ansi16, ..settings(3, false) };
+
+    insta::assert_snapshot!(render(&app(&settings)));
+}
+
+#[test]
+fn terminal_too_small() {
+    let too_small = |settings: &Settings, (width, height): (u16, u16)| matches!(
+        App::new(settings, Rect::new(0, 0, width, height)),
+        Err(BonsaiError::TerminalTooSmall { .. }),
+    );
+
+    assert!(too_small(&settings(1, false), (15, 24)));
+    assert!(too_small(&settings(1, false), (60, 7)));
+    assert!(!too_small(&settings(1, false), (16, 8)));
+
+    assert!(too_small(&Settings { count: 4, ..settings(1, false) }, (63, 24)));
+    assert!(!too_small(&Settings { count: 4, ..settings(1, false) }, (64, 24)));
+    // rejected before thousands of plots are laid out
+    assert!(too_small(&Settings { count: 5000, ..settings(1, false) }, SIZE));
+}
+
+#[test]
+fn slow_growth_still_grows() {
+    // every step takes longer than the lag caught up at once
+    let mut app = app(&Settings { duration: Some(3600.0), ..settings(1, true) });
+    let start = render(&app);
+
+    // a quarter of the growth
+    for _ in 0..3600 * 1000 / 4 / TICK_RATE {
+        app.tick(Duration::from_millis(TICK_RATE), None);
+    }
+
+    assert_ne!(render(&app), start);
+}
@@ -0,0 +1,158 @@
+use proptest::prelude::*;
+use ratatui::layout::Rect;
+use rustbonsai::bonsai::{BonsaiTree, PointType};
+use rustbonsai::bonsai::growth::{Easing, GrowthOrder, GrowthStyle, LeafReveal};
+use rustbonsai::bonsai::seed::parse_seed;
+use rustbonsai::search::{self, Criteria, Lean};
+use rustbonsai::stats::TreeStats;
+
+fn style() -> impl Strategy<Value = GrowthStyle> {
+    let easing = prop_oneof![Just(Easing::Linear), Just(Easing::EaseOut), Just(Easing::Elastic)];
+    let order = prop_oneof![Just(GrowthOrder::Tiered), Just(GrowthOrder::All), Just(GrowthOrder::TrunkFirst)];
+    let reveal = prop_oneof![Just(LeafReveal::Radial), Just(LeafReveal::Sparkle), Just(LeafReveal::TopDown)];
+
+    (easing, order, reveal).prop_map(|(easing, order, reveal)| GrowthStyle { easing, order, reveal })
+}
+
+fn grow(seed: u64, (width, height): (u16, u16), live: bool, style: GrowthStyle, roots: bool) -> BonsaiTree {
+    let mut tree = BonsaiTree::new(Rect::new(0, 0, width, height), Some(seed), live, None, 0.5, style);
+    tree.set_roots(roots);
+    tree.generate();
+    tree.normalize();
+
+    tree
+}
+
+fn in_bounds(points: &[(f64, f64)], (width, height): (u16, u16)) -> bool {
+    points.iter().all(|&(x, y)| (0.0..=width as f64).contains(&x) && (0.0..=height as f64).contains(&y))
+}
+
+#[test]
+fn seeds_grow_the_same_trees() {
+    // changing these means trees saved or shared as seeds grow differently
+    for (text, nodes, leaves) in [("42", 8, 156), (" team-offsite ", 10, 198)] {
+        let tree = grow(parse_seed(text), (60, 24), false, GrowthStyle::default(), false);
+
+        assert_eq!((tree.node_count(), tree.get_leaves().len()), (nodes, leaves), "seed {:?}", text);
+    }
+
+    assert_eq!(parse_seed(" team-offsite "), parse_seed("team-offsite"));
+}
+
+#[test]
+fn search_keeps_the_best_matches() {
+    let criteria = Criteria { min_leaves: Some(300), max_width: Some(0.6), lean: Some(Lean::Left), ..Criteria::default() };
+    let found = search::search(&criteria, 0..200, (120, 40), 5);
+
+    assert!(!found.is_empty() && found.len() <= 5);
+    assert!(found.iter().all(|stats| criteria.accepts(stats)));
+    assert!(found.windows(2).all(|pair| pair[0].lean <= pair[1].lean));
+}
+
+#[test]
+fn roots_grow_first_and_leave_the_tree_alone() {
+    let plain = grow(7, (120, 40), true, GrowthStyle::default(), false);
+    let mut rooted = grow(7, (120, 40), true, GrowthStyle::default(), true);
+
+    assert_eq!(rooted.get_leaves(), plain.get_leaves());
+    assert!(rooted.growth_steps() > plain.growth_steps());
+
+    rooted.animation_step();
+    assert!(!rooted.get_tree().is_empty());
+    assert!(rooted.grown_since(None).1.is_empty());
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn points_within_bounds(seed: u64, width in 16u16..200, height in 8u16..80, roots: bool) {
+        let tree = grow(seed, (width, height), false, GrowthStyle::default(), roots);
+
+        prop_assert!(in_bounds(&tree.get_tree(), (width, height)));
+        prop_assert!(in_bounds(&tree.get_leaves(), (width, height)));
+        prop_assert!(in_bounds(&tree.get_blooms(), (width, height)));
+        prop_assert!(in_bounds(&tree.get_pot(), (width, height)));
+    }
+
+    #[test]
+    fn every_node_reachable_from_root(seed: u64, width in 16u16..200, height in 8u16..80) {
+        let mut tree = BonsaiTree::new(Rect::new(0, 0, width, height), Some(seed), false, None, 0.0, GrowthStyle::default());
+        tree.generate();
+        tree.normalize();
+
+        let mut reached = vec![false; tree.node_count()];
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            prop_assert!(!reached[node], "node {} reached twice", node);
+            reached[node] = true;
+
+            for &child in tree.children(node) {
+                prop_assert_eq!(tree.parent(child), node);
+                stack.push(child);
+            }
+        }
+
+        prop_assert!(reached.iter().all(|&reached| reached));
+    }
+
+    #[test]
+    fn animation_terminates(seed: u64, width in 16u16..120, height in 8u16..50, style in style(), roots: bool, year in proptest::option::of(0u32..200)) {
+        // short years with blooms used to regrow leaves faster than the growth could finish
+        let mut tree = BonsaiTree::new(Rect::new(0, 0, width, height), Some(seed), true, year, 0.5, style);
+        tree.set_roots(roots);
+        tree.generate();
+        tree.normalize();
+
+        let steps = tree.growth_steps();
+
+        for _ in 0..steps {
+            prop_assert!(!tree.is_grown());
+            tree.animation_step();
+        }
+
+        prop_assert!(tree.is_grown());
+        prop_assert_eq!(tree.current_step(), steps);
+    }
+
+    #[test]
+    fn blooms_open_after_the_canopy(seed: u64, width in 16u16..120, height in 8u16..50, style in style()) {
+        let mut tree = grow(seed, (width, height), true, style, false);
+
+        let (mut last_leaf, mut first_bloom, mut blooms) = (0, usize::MAX, 0);
+        for step in 0..tree.growth_steps() {
+            for point in tree.animation_step() {
+                match point {
+                    PointType::Leaf(_) => last_leaf = step,
+                    PointType::Bloom(_) => { first_bloom = usize::min(first_bloom, step); blooms += 1; }
+                    _ => (),
+                }
+            }
+        }
+
+        prop_assert!(tree.is_grown());
+        prop_assert_eq!(blooms, tree.get_blooms().len());
+        prop_assert!(blooms == 0 || first_bloom > last_leaf);
+    }
+
+    #[test]
+    fn stats_add_up(seed: u64, width in 16u16..200, height in 8u16..80) {
+        let stats = TreeStats::new(seed, (width, height));
+        let mut tree = BonsaiTree::new(Rect::new(0, 0, width, height), Some(seed), false, None, 0.0, GrowthStyle::default());
+        tree.generate();
+        tree.normalize();
+
+        prop_assert_eq!(stats.nodes_per_depth.iter().sum::<usize>(), stats.nodes);
+        prop_assert_eq!(stats.nodes_per_tier.values().sum::<usize>(), stats.nodes);
+        prop_assert_eq!(stats.nodes_per_depth[0], 1);
+        prop_assert_eq!(stats.branches(), tree.branch_starts().len());
+        prop_assert_eq!(stats.animation_steps, tree.node_count() + tree.get_leaves().len());
+        prop_assert!(stats.bounding_box.min.0 <= stats.bounding_box.max.0 && stats.bounding_box.max.0 <= width as f64);
+
+        // measured on the whole drawn trunk, not just one point per branch
+        let points: Vec <(f64, f64)> = tree.get_tree().into_iter().chain(tree.get_leaves()).collect();
+        let base = width as f64 / 2.0;
+        let lean = points.iter().map(|&(x, _)| x - base).sum::<f64>() / (points.len() as f64 * base);
+        prop_assert!(f64::abs(stats.lean - lean) < 1e-9);
+    }
+}